@@ -0,0 +1,345 @@
+// Default data served by the mock, shaped after real Firstrade responses.
+
+use serde_json::{Value, json};
+
+pub fn position(symbol: &str, sec_type: u8, quantity: i64, last: f64, unit_cost: f64) -> Value {
+    let cost = unit_cost * quantity as f64 * if sec_type == 2 { 100.0 } else { 1.0 };
+    let market_value = last * quantity as f64 * if sec_type == 2 { 100.0 } else { 1.0 };
+    json!({
+        "quantity": quantity,
+        "last": last,
+        "bid": last - 0.01,
+        "ask": last + 0.01,
+        "vol": 12867180,
+        "close": last,
+        "cost": cost,
+        "unit_cost": unit_cost,
+        "today_share": 0,
+        "today_exe_price": 0,
+        "sec_type": sec_type,
+        "market_value": market_value,
+        "change": 0,
+        "time": "16:00:00",
+        "company_name": format!("{symbol} Inc."),
+        "avg_vol": 0,
+        "eps": 0,
+        "pe": 0,
+        "div_share": 0,
+        "yield": 0,
+        "ex_div_date": "",
+        "div_date": "",
+        "market_cap": 0,
+        "5yr_growth": 0,
+        "beta": 0,
+        "annual_div_rate": 0,
+        "52w_high": 0,
+        "52w_low": 0,
+        "has_lots": false,
+        "asksize": 100,
+        "bidsize": 100,
+        "open_px": 0,
+        "day_high": last,
+        "day_low": last,
+        "purchase_date": "",
+        "day_held": 0,
+        "adj_cost": cost,
+        "adj_unit_cost": unit_cost,
+        "adj_gainloss": market_value - cost,
+        "adj_gainloss_percent": 0,
+        "change_percent": 0,
+        "drip": false,
+        "loan": false,
+        "gainloss": market_value - cost,
+        "gainloss_percent": 0,
+        "symbol": symbol
+    })
+}
+
+pub fn positions() -> Vec<Value> {
+    vec![
+        position("AAPL", 1, 15, 39.63, 39.8),
+        position("MSFT", 1, 10, 510.05, 420.5),
+        position("ABCD260116C00003000", 2, 10, 0.9, 1.05029),
+    ]
+}
+
+pub fn history_item(report_date: &str, trans_str: &str, symbol: &str, quantity: i64, price: f64) -> Value {
+    json!({
+        "report_date": report_date,
+        "trans_str": trans_str,
+        "quantity": quantity,
+        "trade_price": price,
+        "amount": -(quantity as f64) * price,
+        "description": format!("{trans_str} {symbol}"),
+        "descriptionArray": [format!("{trans_str} {symbol}")],
+        "symbol": symbol,
+        "account_type": "1",
+        "shortDesc": null
+    })
+}
+
+pub fn history() -> Vec<Value> {
+    vec![
+        history_item("2025-08-01", "Bought", "AAPL", 10, 39.5),
+        history_item("2025-08-04", "Bought", "AAPL", 5, 40.4),
+        history_item("2025-08-05", "Sold", "MSFT", -2, 512.0),
+        history_item("2025-08-15", "Dividend", "MSFT", 0, 0.0),
+        history_item("2025-08-20", "Interest", "", 0, 0.0),
+    ]
+}
+
+pub fn stock_quote(symbol: &str, last: f64) -> Value {
+    json!({
+        "symbol": symbol,
+        "sec_type": 1,
+        "tick": "N",
+        "bid": last - 0.01,
+        "bid_size": 900,
+        "ask": last + 0.01,
+        "ask_size": 207,
+        "last": last,
+        "change": -0.121,
+        "high": last + 1.0,
+        "low": last - 1.0,
+        "bid_mmid": "BLUE",
+        "ask_mmid": "BLUE",
+        "last_mmid": "XADF",
+        "last_size": 3000,
+        "change_color": "red",
+        "vol": 27510424,
+        "today_close": last,
+        "prev_close": last,
+        "show_close": "Y",
+        "change_percent": -0.21,
+        "margin_long_req": 50,
+        "margin_short_req": 50,
+        "open": 0,
+        "quote_time": "08:00:12 pm",
+        "last_trade_time": "07:59 pm",
+        "company_name": format!("{symbol} Inc."),
+        "mssecid": "",
+        "exchange": "NASDAQ",
+        "has_option": true,
+        "is_etf": false,
+        "is_fractional": true,
+        "is_overnight": true,
+        "realtime": "T",
+        "nls": "F",
+        "shares": 0
+    })
+}
+
+pub fn option_quote(
+    symbol: &str,
+    underlying: &str,
+    call: bool,
+    strike: f64,
+    exp_date: &str,
+    last: f64,
+) -> Value {
+    json!({
+        "symbol": symbol,
+        "sec_type": 2,
+        "underlying_symbol": underlying,
+        "class": if call { "CALL" } else { "PUT" },
+        "tick": "N",
+        "strike_price": strike,
+        "exp_date": exp_date,
+        "bid": last - 0.05,
+        "bid_size": 17,
+        "ask": last + 0.05,
+        "ask_size": 238,
+        "last": last,
+        "change": 0.1,
+        "high": last,
+        "low": last,
+        "change_color": "green",
+        "vol": 26,
+        "quote_time": "03:38:57 pm",
+        "exchange": "OPRA",
+        "realtime": "T",
+        "shares": 0
+    })
+}
+
+pub fn quotes() -> Vec<(String, Value)> {
+    vec![
+        ("AAPL".to_string(), stock_quote("AAPL", 39.63)),
+        ("MSFT".to_string(), stock_quote("MSFT", 510.05)),
+        ("BRK.B".to_string(), stock_quote("BRK.B", 470.12)),
+        (
+            "ABCD260116C00003000".to_string(),
+            option_quote("ABCD260116C00003000", "ABCD", true, 3.0, "20260116", 0.9),
+        ),
+    ]
+}
+
+pub fn option_expirations() -> Vec<Value> {
+    vec![
+        json!({ "exp_date": "20250919", "day_left": 3, "exp_type": "M" }),
+        json!({ "exp_date": "20250926", "day_left": 10, "exp_type": "W" }),
+        json!({ "exp_date": "20251017", "day_left": 31, "exp_type": "M" }),
+    ]
+}
+
+/// OCC symbol, e.g. `SPY250919C00600000`.
+pub fn occ_symbol(root: &str, exp_date: &str, call: bool, strike: f64) -> String {
+    let class = if call { 'C' } else { 'P' };
+    format!(
+        "{root}{}{class}{:08}",
+        &exp_date[2..],
+        (strike * 1000.0).round() as u64
+    )
+}
+
+/// Five strikes around `last`, each with a call and a put.
+pub fn option_chain(root: &str, exp_date: &str, last: f64) -> Vec<Value> {
+    let step = if last < 100.0 { 1.0 } else { 5.0 };
+    let center = (last / step).round() * step;
+    (-2..=2)
+        .map(|i| {
+            let strike = center + i as f64 * step;
+            let contract = |call: bool| {
+                let intrinsic = if call { last - strike } else { strike - last };
+                let price = (intrinsic.max(0.0) + 1.0).round();
+                json!({
+                    "symbol": occ_symbol(root, exp_date, call, strike),
+                    "bid": price - 0.05,
+                    "bid_size": 12,
+                    "ask": price + 0.05,
+                    "ask_size": 30,
+                    "last": price,
+                    "change": 0.1,
+                    "vol": 100 + i * 10,
+                    "open_interest": 2000 + i * 100
+                })
+            };
+            json!({
+                "strike_price": strike,
+                "call": contract(true),
+                "put": contract(false)
+            })
+        })
+        .collect()
+}
+
+pub fn option_greeks(chain: &[Value]) -> Vec<Value> {
+    chain
+        .iter()
+        .flat_map(|strike| [&strike["call"], &strike["put"]])
+        .map(|contract| {
+            let symbol = contract["symbol"].as_str().unwrap_or_default();
+            let call = symbol.len() > 9 && symbol.as_bytes()[symbol.len() - 9] == b'C';
+            json!({
+                "symbol": symbol,
+                "delta": if call { 0.5 } else { -0.5 },
+                "gamma": 0.03,
+                "theta": -0.4,
+                "vega": 0.2,
+                "rho": if call { 0.05 } else { -0.05 },
+                "iv": 0.18
+            })
+        })
+        .collect()
+}
+
+pub fn ohlc(symbol: &str, points: usize, with_volume: bool) -> Value {
+    let start = 1754472600000i64;
+    let ohlc: Vec<Value> = (0..points)
+        .map(|i| {
+            let price = 40.0 + i as f64;
+            let mut entry = vec![
+                json!(start + i as i64 * 60_000),
+                json!(price),
+                json!(price + 0.5),
+                json!(price - 0.5),
+                json!(price + 0.25),
+            ];
+            if with_volume {
+                entry.push(json!(1000 + i));
+            }
+            Value::Array(entry)
+        })
+        .collect();
+    let vol: Vec<Value> = (0..points)
+        .map(|i| json!([start + i as i64 * 60_000, 1000 + i]))
+        .collect();
+    json!({
+        "ohlc": ohlc,
+        "vol": vol,
+        "prev_close": 40.34,
+        "symbol": symbol
+    })
+}
+
+pub fn balances(account: &str) -> Value {
+    json!({
+        "account": account,
+        "freetrade_count": 0,
+        "last_freetrade_date": "",
+        "total_account_value": 12345678.11,
+        "total_account_change": 8652.99,
+        "long_stock_change": 8652.99,
+        "short_stock_change": 0,
+        "long_option_change": 0,
+        "short_option_change": 0,
+        "fixed_income_change": 0,
+        "mutual_funds_change": 0,
+        "cash_balance": 12345.67,
+        "cash_balance_change": 0,
+        "margin_balance": -123.11,
+        "margin_balance_change": 123,
+        "margin_buying_power": 385708.77,
+        "long_stock_value": 567890.11,
+        "long_option_value": 56789.11,
+        "short_option_value": -12345,
+        "non_margin_buying_power": 192854.39,
+        "daytrade_buying_power": 500000.11,
+        "money_locked_by_pending_orders": 0
+    })
+}
+
+pub fn account_item(account: &str, option_level: i64) -> Value {
+    json!({
+        "account": account,
+        "alias": "Individual",
+        "permissions": "LV",
+        "type": "Margin",
+        "ext_hours_trading_status": "Y",
+        "signed_fractional": "Y",
+        "total_value": 12345678.11,
+        "option_level": option_level,
+        "default": true
+    })
+}
+
+pub fn user_info(account: &str, sid: &str, ftat: &str) -> Value {
+    json!({
+        "sid": sid,
+        "ftat": ftat,
+        "onbehalf_id": "FT00012345",
+        "eui": "A_9A32677D15A930269279AA9F09FADB263F34859556C0D9901062C9B18318D123",
+        "realtime_quote": true,
+        "streaming_quote": false,
+        "real_time_index_quotes_status": true,
+        "real_time_watchlist_quote": true,
+        "nls_quote": true,
+        "authenticated": true,
+        "accounts": [account],
+        "primary_accounts": [account],
+        "admin_accounts": [account],
+        "selected_account": account,
+        "locale": "en-us",
+        "menu": {
+            "promotion": false,
+            "contact": true,
+            "funding": true,
+            "terms": true,
+            "tutorials": true,
+            "acats": true
+        },
+        "edoc": {
+            "show_reminder": true
+        }
+    })
+}
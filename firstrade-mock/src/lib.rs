@@ -0,0 +1,63 @@
+//! A local stand-in for the Firstrade API.
+//!
+//! Serves login (MFA, OTP and PIN), accounts, positions, balances, history, quotes, OHLC,
+//! watchlists and orders from stateful fixtures, with failures that can be injected per route.
+#![recursion_limit = "256"]
+#![allow(clippy::result_large_err)]
+
+pub mod fixtures;
+mod routes;
+mod state;
+
+pub use state::{Failure, LoginFlow, MockConfig, MockState, Order, OtpRecipient};
+
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// A mock server listening on a random local port, stopped when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: MockState,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        Self::start_with(MockConfig::default()).await
+    }
+
+    pub async fn start_with(config: MockConfig) -> Self {
+        let state = MockState::new(config);
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let addr = listener.local_addr().expect("Failed to get mock server address");
+
+        let router = routes::router(state.clone());
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, router).await.expect("mock server failed");
+        });
+
+        Self { addr, state, handle }
+    }
+
+    /// Base url to configure the SDK `Endpoint` with, e.g. `http://127.0.0.1:49152`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn state(&self) -> &MockState {
+        &self.state
+    }
+
+    pub fn config(&self) -> MockConfig {
+        self.state.lock().config.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
@@ -0,0 +1,950 @@
+use crate::fixtures;
+use crate::state::{Failure, Inner, LoginFlow, MockState, Order, Watchlist, WatchlistItem};
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+
+type Params = HashMap<String, String>;
+
+pub(crate) fn router(state: MockState) -> Router {
+    Router::new()
+        .route("/sess/login", post(login))
+        .route("/sess/verify_pin", post(verify_pin))
+        .route("/sess/request_code", post(request_code))
+        .route("/public/market_time", get(market_time))
+        .route("/public/quote", get(quote))
+        .route("/public/ohlc", get(ohlc))
+        .route("/public/mohlc", get(mohlc))
+        .route("/public/oc", get(option_chain))
+        .route("/private/acct_list", get(account_list))
+        .route("/private/userinfo", get(user_info))
+        .route("/private/balances", get(balances))
+        .route("/private/account_history", get(account_history))
+        .route("/private/positions", get(positions))
+        .route("/private/watchlists", get(watchlists).post(add_watchlist))
+        .route(
+            "/private/watchlists/{id}",
+            get(watchlist_quote).delete(delete_watchlist),
+        )
+        .route(
+            "/private/watchlist/{id}",
+            post(watchlist_add_symbol).delete(watchlist_remove_symbol),
+        )
+        .route("/private/stock_order", post(stock_order))
+        .route("/private/option_order", post(option_order))
+        .route("/private/option_spread_order", post(option_spread_order))
+        .route("/private/order_status", get(order_status))
+        .route("/private/cancel_order", post(cancel_order))
+        .route("/private/replace_order", post(replace_order))
+        .route("/private/greekoptions/analytical", post(option_greeks))
+        .layer(middleware::from_fn_with_state(state.clone(), guard))
+        .with_state(state)
+}
+
+// Records the request, then applies injected failures and the session check of private routes.
+async fn guard(State(state): State<MockState>, req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_string();
+    let (failure, authorized) = {
+        let mut inner = state.lock();
+        inner.requests.push((req.method().to_string(), path.clone()));
+        let failure = inner.failures.get_mut(&path).and_then(|queue| queue.pop_front());
+
+        let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+        let authorized = !path.starts_with("/private")
+            || (inner.sid.is_some()
+                && header("sid") == inner.sid.as_deref()
+                && header("ftat") == inner.ftat.as_deref());
+        (failure, authorized)
+    };
+
+    match failure {
+        Some(failure) => failure_response(failure),
+        None if !authorized => unauthorized(),
+        None => next.run(req).await,
+    }
+}
+
+fn failure_response(failure: Failure) -> Response {
+    match failure {
+        Failure::Unauthorized => unauthorized(),
+        Failure::Status(code) => {
+            let status = StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            (status, status.canonical_reason().unwrap_or("error").to_string()).into_response()
+        }
+        Failure::ErrorBody {
+            status_code,
+            error,
+            message,
+        } => normal_with(status_code, &error, &message, json!({})),
+    }
+}
+
+fn unauthorized() -> Response {
+    error_response(
+        StatusCode::UNAUTHORIZED,
+        "Unauthorized",
+        "Invalid or expired session",
+    )
+}
+
+fn error_response(status: StatusCode, error: &str, message: &str) -> Response {
+    let body = json!({
+        "statusCode": status.as_u16(),
+        "error": error,
+        "message": message,
+    });
+    (status, axum::Json(body)).into_response()
+}
+
+fn normal_with(status_code: u16, error: &str, message: &str, fields: Value) -> Response {
+    let mut body = Map::new();
+    body.insert("statusCode".to_string(), json!(status_code));
+    body.insert("error".to_string(), json!(error));
+    body.insert("message".to_string(), json!(message));
+    if let Value::Object(fields) = fields {
+        body.extend(fields);
+    }
+    axum::Json(Value::Object(body)).into_response()
+}
+
+/// `200 Normal` response with `fields` merged into the envelope.
+pub(crate) fn normal(fields: Value) -> Response {
+    normal_with(200, "", "Normal", fields)
+}
+
+/// `200` response carrying an error, the way Firstrade reports most business errors.
+pub(crate) fn normal_error(status_code: u16, error: &str, message: &str) -> Response {
+    normal_with(status_code, error, message, json!({}))
+}
+
+/// Form or JSON body, both are used by the login endpoints.
+pub(crate) fn parse_body(body: &Bytes) -> Params {
+    if let Ok(Value::Object(map)) = serde_json::from_slice::<Value>(body) {
+        return map
+            .into_iter()
+            .map(|(k, v)| match v {
+                Value::String(s) => (k, s),
+                v => (k, v.to_string()),
+            })
+            .collect();
+    }
+    serde_urlencoded::from_bytes(body).unwrap_or_default()
+}
+
+fn verified(inner: &mut Inner) -> Response {
+    if inner.ftat.is_none() {
+        inner.ftat = Some(inner.new_token("FTAT"));
+    }
+    let sid = inner.new_token("SID");
+    inner.sid = Some(sid.clone());
+    inner.t_tokens.clear();
+    inner.verifications.clear();
+
+    normal(json!({
+        "sid": sid,
+        "ftat": inner.ftat,
+        "onbehalf_id": "FT00012345",
+        "eui": "A_9A32677D15A930269279AA9F09FADB263F34859556C0D9901062C9B18318D911",
+        "realtime_quote": true,
+        "streaming_quote": false,
+        "real_time_index_quotes_status": true,
+        "real_time_watchlist_quote": true,
+        "nls_quote": true
+    }))
+}
+
+// ==================== Session ====================
+async fn login(State(state): State<MockState>, headers: HeaderMap, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    if params.get("username") != Some(&inner.config.username)
+        || params.get("password") != Some(&inner.config.password)
+    {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Bad Request",
+            "Invalid username or password",
+        );
+    }
+
+    // A remembered ftat skips the second factor, this is also how the sid is renewed
+    let ftat = headers.get("ftat").and_then(|v| v.to_str().ok());
+    if ftat.is_some() && ftat == inner.ftat.as_deref() {
+        return verified(&mut inner);
+    }
+
+    let t_token = inner.new_token("T");
+    inner.t_tokens.insert(t_token.clone());
+    let recipients: Vec<Value> = inner
+        .config
+        .otp_recipients
+        .iter()
+        .map(|r| {
+            json!({
+                "recipientId": r.recipient_id,
+                "channel": r.channel,
+                "recipientMask": r.recipient_mask,
+            })
+        })
+        .collect();
+
+    match inner.config.login_flow.clone() {
+        LoginFlow::Mfa => normal(json!({ "t_token": t_token, "mfa": true })),
+        LoginFlow::Otp { pin_fallback } => normal(json!({
+            "t_token": t_token,
+            "mfa": false,
+            "otp": recipients,
+            "fallback": { "strategy": "pin", "pinDisabled": !pin_fallback }
+        })),
+        LoginFlow::Pin { pin_disabled } => normal(json!({
+            "t_token": t_token,
+            "mfa": false,
+            "otp": [],
+            "fallback": { "strategy": "pin", "pinDisabled": pin_disabled }
+        })),
+    }
+}
+
+async fn verify_pin(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    let t_token = params.get("t_token").cloned().unwrap_or_default();
+    if !inner.t_tokens.contains(&t_token) {
+        return error_response(StatusCode::BAD_REQUEST, "Bad Request", "Invalid t_token");
+    }
+
+    let accepted = if let Some(code) = params.get("mfaCode") {
+        inner.config.login_flow == LoginFlow::Mfa
+            && match &inner.config.mfa_code {
+                Some(expected) => code == expected,
+                None => code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()),
+            }
+    } else if let Some(code) = params.get("otpCode") {
+        let verification = params
+            .get("verificationSid")
+            .and_then(|sid| inner.verifications.get(sid));
+        verification == Some(&t_token) && code == &inner.config.otp_code
+    } else if let Some(pin) = params.get("pin") {
+        let pin_enabled = matches!(
+            inner.config.login_flow,
+            LoginFlow::Otp { pin_fallback: true } | LoginFlow::Pin { pin_disabled: false }
+        );
+        pin_enabled && pin == &inner.config.pin
+    } else {
+        false
+    };
+
+    if accepted {
+        verified(&mut inner)
+    } else {
+        error_response(
+            StatusCode::BAD_REQUEST,
+            "Bad Request",
+            "Invalid verification code",
+        )
+    }
+}
+
+async fn request_code(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    let t_token = params.get("t_token").cloned().unwrap_or_default();
+    let recipient_id = params.get("recipientId").and_then(|id| id.parse::<i64>().ok());
+    let known_recipient = inner
+        .config
+        .otp_recipients
+        .iter()
+        .any(|r| Some(r.recipient_id) == recipient_id);
+    if !inner.t_tokens.contains(&t_token) || !known_recipient {
+        return normal_error(400, "Bad Request", "Invalid recipient");
+    }
+
+    let verification_sid = inner.new_token("VE");
+    inner.verifications.insert(verification_sid.clone(), t_token);
+    normal(json!({ "verificationSid": verification_sid }))
+}
+
+// ==================== Account ====================
+async fn market_time() -> Response {
+    normal(json!({
+        "result": {
+            "is_trading_date": true,
+            "seconds_till_close": 18979,
+            "current_date": "20250731",
+            "current_date_dash": "2025-07-31"
+        }
+    }))
+}
+
+async fn account_list(State(state): State<MockState>) -> Response {
+    let inner = state.lock();
+    normal(json!({
+        "items": [fixtures::account_item(&inner.config.account, inner.config.option_level)],
+        "grand_total": 12345678.11
+    }))
+}
+
+async fn user_info(State(state): State<MockState>) -> Response {
+    let inner = state.lock();
+    let body = fixtures::user_info(
+        &inner.config.account,
+        inner.sid.as_deref().unwrap_or_default(),
+        inner.ftat.as_deref().unwrap_or_default(),
+    );
+    axum::Json(body).into_response()
+}
+
+async fn balances(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    if params.get("account") != Some(&inner.config.account) {
+        return normal_error(400, "Bad Request", "Invalid account");
+    }
+    normal(json!({ "result": fixtures::balances(&inner.config.account) }))
+}
+
+fn page_params(params: &Params, default_per_page: usize) -> (usize, usize) {
+    let page = params
+        .get("page")
+        .and_then(|p| p.parse().ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(1);
+    let per_page = params
+        .get("per_page")
+        .and_then(|p| p.parse().ok())
+        .filter(|p| *p > 0)
+        .unwrap_or(default_per_page);
+    (page, per_page)
+}
+
+fn page_of(items: &[Value], page: usize, per_page: usize) -> Vec<Value> {
+    items
+        .iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .cloned()
+        .collect()
+}
+
+async fn account_history(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    if params.get("account") != Some(&inner.config.account) {
+        return normal_error(400, "Bad Request", "Invalid account");
+    }
+    let (page, per_page) = page_params(&params, 200);
+    normal(json!({
+        "items": page_of(&inner.history, page, per_page),
+        "per_page": per_page,
+        "page": page,
+        "total": inner.history.len()
+    }))
+}
+
+async fn positions(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    if params.get("account") != Some(&inner.config.account) {
+        return normal_error(400, "Bad Request", "Invalid account");
+    }
+    let (page, per_page) = page_params(&params, 200);
+    let total = inner.positions.len();
+    let pages = total.div_ceil(per_page).max(1);
+    let sum = |field: &str| -> f64 { inner.positions.iter().filter_map(|p| p[field].as_f64()).sum() };
+
+    normal(json!({
+        "page": page,
+        "pages": pages,
+        "per_page": per_page,
+        "total": total,
+        "realtime": "T",
+        "items": page_of(&inner.positions, page, per_page),
+        "total_market_value": sum("market_value"),
+        "total_gainloss": sum("gainloss"),
+        "total_gainloss_percent": if sum("cost") == 0.0 {
+            0.0
+        } else {
+            (sum("gainloss") / sum("cost") * 1_000_000.0).round() / 10_000.0
+        },
+        "total_daychange_amount": 0,
+        "total_daychange_percent": 0,
+        "isCostBasisReady": true,
+        "account": inner.config.account,
+        "pagination": {}
+    }))
+}
+
+// ==================== Quote ====================
+async fn quote(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    let symbol = params.get("q").cloned().unwrap_or_default();
+    match inner.quotes.get(&symbol) {
+        Some(quote) => normal(json!({ "result": quote })),
+        None => normal_error(400, "Bad Request", "Invalid symbol"),
+    }
+}
+
+async fn ohlc(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    let symbol = params.get("symbol").cloned().unwrap_or_default();
+    if !inner.quotes.contains_key(&symbol) {
+        return normal_error(400, "Bad Request", "Invalid symbol");
+    }
+    let mut result = fixtures::ohlc(&symbol, 5, true);
+    result["range"] = json!(params.get("range"));
+    normal(json!({ "result": result }))
+}
+
+async fn mohlc(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    let resolution: usize = params.get("resolution").and_then(|r| r.parse().ok()).unwrap_or(0);
+    if resolution == 0 || resolution > 50 {
+        return normal_error(400, "Bad Request", "Invalid resolution");
+    }
+
+    let symbols: Vec<&str> = params
+        .get("symbols")
+        .map(|s| s.split(',').collect())
+        .unwrap_or_default();
+    if inner
+        .config
+        .mohlc_max_symbols
+        .is_some_and(|max| symbols.len() > max)
+    {
+        return normal_error(400, "Bad Request", "Too many symbols");
+    }
+
+    let result: Map<String, Value> = symbols
+        .into_iter()
+        .filter(|symbol| inner.quotes.contains_key(*symbol))
+        .map(|symbol| {
+            let mut ohlc = fixtures::ohlc(symbol, resolution, false);
+            ohlc.as_object_mut().unwrap().remove("symbol");
+            (symbol.to_string(), ohlc)
+        })
+        .collect();
+    normal(json!({ "result": result }))
+}
+
+// ==================== Option chain ====================
+fn chain_of(inner: &Inner, params: &Params) -> Result<Vec<Value>, Response> {
+    let root = params.get("root_symbol").cloned().unwrap_or_default();
+    let exp_date = params.get("exp_date").cloned().unwrap_or_default();
+    let last = inner
+        .quotes
+        .get(&root)
+        .filter(|quote| quote["sec_type"] == 1)
+        .and_then(|quote| quote["last"].as_f64())
+        .ok_or_else(|| normal_error(400, "Bad Request", "Invalid symbol"))?;
+    if !fixtures::option_expirations()
+        .iter()
+        .any(|exp| exp["exp_date"] == exp_date.as_str())
+    {
+        return Err(normal_error(400, "Bad Request", "Invalid expiration date"));
+    }
+    Ok(fixtures::option_chain(&root, &exp_date, last))
+}
+
+async fn option_chain(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    match params.get("m").map(String::as_str) {
+        Some("get_exp_dates") => {
+            let root = params.get("root_symbol").cloned().unwrap_or_default();
+            match inner.quotes.get(&root) {
+                Some(quote) if quote["sec_type"] == 1 => {
+                    normal(json!({ "items": fixtures::option_expirations() }))
+                }
+                _ => normal_error(400, "Bad Request", "Invalid symbol"),
+            }
+        }
+        Some("get_oc") => match chain_of(&inner, &params) {
+            Ok(chain) => normal(json!({ "items": chain })),
+            Err(resp) => resp,
+        },
+        _ => normal_error(400, "Bad Request", "Invalid method"),
+    }
+}
+
+async fn option_greeks(State(state): State<MockState>, body: Bytes) -> Response {
+    let inner = state.lock();
+    match chain_of(&inner, &parse_body(&body)) {
+        Ok(chain) => normal(json!({ "result": fixtures::option_greeks(&chain) })),
+        Err(resp) => resp,
+    }
+}
+
+// ==================== Watchlist ====================
+async fn watchlists(State(state): State<MockState>) -> Response {
+    let inner = state.lock();
+    let items: Vec<Value> = inner
+        .watchlists
+        .iter()
+        .map(|(id, list)| json!({ "list_id": id, "name": list.name, "isDefault": list.is_default }))
+        .collect();
+    normal(json!({ "items": items }))
+}
+
+async fn add_watchlist(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let Some(name) = params.get("name").filter(|n| !n.is_empty()) else {
+        return normal_error(400, "Bad Request", "Missing name");
+    };
+
+    let mut inner = state.lock();
+    let list_id = inner.next_id() as i64;
+    inner.watchlists.insert(
+        list_id,
+        Watchlist {
+            name: name.clone(),
+            is_default: false,
+            items: Vec::new(),
+        },
+    );
+    normal(json!({ "result": { "list_id": list_id, "result": "success" } }))
+}
+
+async fn watchlist_quote(State(state): State<MockState>, Path(id): Path<i64>) -> Response {
+    let inner = state.lock();
+    let Some(list) = inner.watchlists.get(&id) else {
+        return normal_error(404, "Not Found", "Watchlist not found");
+    };
+
+    let list_items: Vec<Value> = list
+        .items
+        .iter()
+        .map(|item| {
+            let quote = inner.quotes.get(&item.symbol).cloned().unwrap_or(json!({}));
+            let num = |field: &str| quote[field].as_f64().unwrap_or_default();
+            json!({
+                "watchlist_id": item.item_id,
+                "sec_type": item.sec_type,
+                "symbol": item.symbol,
+                "quantity": 0,
+                "last": num("last"),
+                "bid": num("bid"),
+                "ask": num("ask"),
+                "vol": quote["vol"].as_i64().unwrap_or_default(),
+                "change": num("change"),
+                "change_percent": num("change_percent"),
+                "day_gain_amount": 0,
+                "unit_cost": 0,
+                "cost": 0,
+                "gain_amount": 0,
+                "gain_percent": 0,
+                "bidsize": quote["bid_size"].as_i64().unwrap_or_default(),
+                "asksize": quote["ask_size"].as_i64().unwrap_or_default(),
+                "high": num("high"),
+                "low": num("low"),
+                "close_price": num("prev_close"),
+                "open_price": num("open"),
+                "update_time": quote["quote_time"].as_str().unwrap_or_default()
+            })
+        })
+        .collect();
+
+    normal(json!({
+        "result": { "list_id": id, "name": list.name, "list_items": list_items }
+    }))
+}
+
+async fn delete_watchlist(State(state): State<MockState>, Path(id): Path<i64>) -> Response {
+    let mut inner = state.lock();
+    match inner.watchlists.remove(&id) {
+        Some(_) => normal(json!({ "result": { "list_id": id, "result": "success" } })),
+        None => normal_error(404, "Not Found", "Watchlist not found"),
+    }
+}
+
+async fn watchlist_add_symbol(State(state): State<MockState>, Path(id): Path<i64>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+    let item_id = inner.next_id() as i64;
+
+    let Some(symbol) = params.get("symbol").filter(|s| !s.is_empty()) else {
+        return normal_error(400, "Bad Request", "Missing symbol");
+    };
+    if !inner.quotes.contains_key(symbol) {
+        return normal_error(400, "Bad Request", "Invalid symbol");
+    }
+    let Some(list) = inner.watchlists.get_mut(&id) else {
+        return normal_error(404, "Not Found", "Watchlist not found");
+    };
+    let sec_type = params.get("sec_type").and_then(|s| s.parse().ok()).unwrap_or(1);
+
+    list.items.push(WatchlistItem {
+        item_id,
+        symbol: symbol.clone(),
+        sec_type,
+    });
+    normal(json!({ "result": { "watchlist_id": item_id, "result": "success" } }))
+}
+
+async fn watchlist_remove_symbol(State(state): State<MockState>, Path(item_id): Path<i64>) -> Response {
+    let mut inner = state.lock();
+    let removed = inner.watchlists.values_mut().any(|list| {
+        let before = list.items.len();
+        list.items.retain(|item| item.item_id != item_id);
+        list.items.len() != before
+    });
+
+    if removed {
+        normal(json!({ "result": { "watchlist_id": item_id, "result": "success" } }))
+    } else {
+        normal_error(404, "Not Found", "Symbol not found")
+    }
+}
+
+// ==================== Order ====================
+fn parse_price(params: &Params, field: &str) -> Option<f64> {
+    params
+        .get(field)
+        .and_then(|p| p.parse::<f64>().ok())
+        .filter(|p| p.is_finite() && *p > 0.0)
+}
+
+// Validates an order form, returning the order to place, the last price and the preview result.
+fn check_order(inner: &Inner, params: &Params, option: bool) -> Result<(Order, f64, Value), Response> {
+    if params.get("account") != Some(&inner.config.account) {
+        return Err(normal_error(400, "Bad Request", "Invalid account"));
+    }
+    let symbol = params.get("symbol").cloned().unwrap_or_default();
+    let sec_type = if option { 2 } else { 1 };
+    let Some(last) = inner
+        .quotes
+        .get(&symbol)
+        .filter(|q| q["sec_type"] == sec_type)
+        .and_then(|q| q["last"].as_f64())
+    else {
+        return Err(normal_error(400, "Invalid symbol", "Symbol not found"));
+    };
+    let transaction = params.get("transaction").cloned().unwrap_or_default();
+    let transactions: &[&str] = if option {
+        &["BO", "SO", "BC", "S"]
+    } else {
+        &["B", "S", "SS", "BC"]
+    };
+    if !transactions.contains(&transaction.as_str()) {
+        return Err(normal_error(
+            400,
+            "Invalid transaction",
+            "Unknown transaction type",
+        ));
+    }
+    if option && transaction == "BO" && inner.config.option_level < 2 {
+        return Err(normal_error(403, "Option level", "Option level 2 required"));
+    }
+    let Some(quantity) = parse_price(params, if option { "contracts" } else { "shares" }) else {
+        return Err(normal_error(400, "Invalid quantity", "Quantity must be positive"));
+    };
+    let duration = params.get("duration").cloned().unwrap_or_default();
+    if !matches!(duration.as_str(), "0" | "1") {
+        return Err(normal_error(400, "Invalid duration", "Unknown duration"));
+    }
+
+    let price_type = params.get("price_type").cloned().unwrap_or_default();
+    let limit_price = parse_price(params, "limit_price");
+    let stop_price = parse_price(params, "stop_price");
+    let valid_prices = match price_type.as_str() {
+        "1" => true,
+        "2" => limit_price.is_some(),
+        "3" => stop_price.is_some(),
+        "4" => limit_price.is_some() && stop_price.is_some(),
+        _ => false,
+    };
+    if !valid_prices {
+        return Err(normal_error(400, "Invalid price", "Missing or invalid price"));
+    }
+
+    let multiplier = if option { 100.0 } else { 1.0 };
+    let principal = quantity * multiplier * limit_price.or(stop_price).unwrap_or(last);
+    let buying_power = fixtures::balances(&inner.config.account)["non_margin_buying_power"]
+        .as_f64()
+        .unwrap_or_default();
+    if matches!(transaction.as_str(), "B" | "BC" | "BO") && principal > buying_power {
+        return Err(normal_error(
+            400,
+            "Insufficient buying power",
+            "Order exceeds buying power",
+        ));
+    }
+
+    let order = Order {
+        order_id: String::new(),
+        symbol,
+        transaction,
+        quantity,
+        price_type,
+        limit_price,
+        stop_price,
+        duration,
+        status: "Open".to_string(),
+        fills: Vec::new(),
+        reject_reason: None,
+        legs: Vec::new(),
+    };
+    let preview = json!({
+        "principal": principal,
+        "commission": 0.0,
+        "fee": 0.0,
+        "estimated_total": principal,
+        "buying_power": buying_power,
+        "warnings": []
+    });
+    Ok((order, last, preview))
+}
+
+fn is_preview(params: &Params) -> bool {
+    params.get("preview").map(String::as_str) == Some("true")
+}
+
+fn place_order(inner: &mut Inner, mut order: Order, last: f64) -> String {
+    order.order_id = inner.next_id().to_string();
+    // Market orders fill right away, the others rest until filled from the test
+    if order.price_type == "1" {
+        order.fill(order.quantity, last);
+    }
+    let order_id = order.order_id.clone();
+    inner.orders.push(order);
+    order_id
+}
+
+async fn stock_order(State(state): State<MockState>, body: Bytes) -> Response {
+    place(state, body, false)
+}
+
+async fn option_order(State(state): State<MockState>, body: Bytes) -> Response {
+    place(state, body, true)
+}
+
+fn place(state: MockState, body: Bytes, option: bool) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    let (order, last, preview) = match check_order(&inner, &params, option) {
+        Ok(checked) => checked,
+        Err(resp) => return resp,
+    };
+    if is_preview(&params) {
+        return normal(json!({ "result": preview }));
+    }
+
+    let order_id = place_order(&mut inner, order, last);
+    normal(json!({ "result": { "order_id": order_id, "result": "success" } }))
+}
+
+async fn replace_order(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    let (mut order, last, preview) = match check_order(&inner, &params, false) {
+        Ok(checked) => checked,
+        Err(resp) => return resp,
+    };
+    let original_id = params.get("orig_order_id").cloned().unwrap_or_default();
+    let Some(original) = inner.orders.iter().find(|o| o.order_id == original_id) else {
+        return normal_error(404, "Not Found", "Order not found");
+    };
+    if !matches!(original.status.as_str(), "Open" | "Partial Filled") {
+        return normal_error(400, "Order cannot be replaced", "Order is no longer open");
+    }
+    if original.symbol != order.symbol || original.transaction != order.transaction {
+        return normal_error(400, "Order cannot be replaced", "Symbol and side cannot change");
+    }
+    if order.quantity <= original.executed_quantity() {
+        return normal_error(
+            400,
+            "Invalid quantity",
+            "Quantity must exceed the executed quantity",
+        );
+    }
+    if is_preview(&params) {
+        return normal(json!({ "result": preview }));
+    }
+
+    // The replacement carries the executions of the original order
+    order.fills = original.fills.clone();
+    if !order.fills.is_empty() {
+        order.status = "Partial Filled".to_string();
+    }
+    if let Some(original) = inner.orders.iter_mut().find(|o| o.order_id == original_id) {
+        original.status = "Replaced".to_string();
+    }
+    let order_id = place_order(&mut inner, order, last);
+    normal(json!({
+        "result": { "order_id": order_id, "original_order_id": original_id, "result": "success" }
+    }))
+}
+
+async fn option_spread_order(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+
+    if params.get("account") != Some(&inner.config.account) {
+        return normal_error(400, "Bad Request", "Invalid account");
+    }
+    let leg_count: usize = params
+        .get("leg_count")
+        .and_then(|n| n.parse().ok())
+        .unwrap_or_default();
+    if !(2..=4).contains(&leg_count) {
+        return normal_error(400, "Invalid legs", "A spread needs 2 to 4 legs");
+    }
+
+    let mut legs = Vec::new();
+    let mut market_net = 0.0;
+    for i in 1..=leg_count {
+        let symbol = params.get(&format!("symbol{i}")).cloned().unwrap_or_default();
+        let transaction = params
+            .get(&format!("transaction{i}"))
+            .cloned()
+            .unwrap_or_default();
+        let ratio: u32 = params
+            .get(&format!("ratio{i}"))
+            .and_then(|r| r.parse().ok())
+            .unwrap_or_default();
+        let Some(last) = inner
+            .quotes
+            .get(&symbol)
+            .filter(|q| q["sec_type"] == 2)
+            .and_then(|q| q["last"].as_f64())
+        else {
+            return normal_error(400, "Invalid symbol", "Symbol not found");
+        };
+        let sign = match transaction.as_str() {
+            "BO" | "BC" => 1.0,
+            "SO" | "S" => -1.0,
+            _ => return normal_error(400, "Invalid transaction", "Unknown transaction type"),
+        };
+        if ratio == 0 {
+            return normal_error(400, "Invalid ratio", "Ratio must be positive");
+        }
+        market_net += sign * last * f64::from(ratio);
+        legs.push((symbol, transaction, ratio));
+    }
+
+    let transactions = || legs.iter().map(|(_, t, _)| t.as_str());
+    let required_level = if transactions().any(|t| t == "SO") {
+        3
+    } else if transactions().any(|t| t == "BO") {
+        2
+    } else {
+        1
+    };
+    if inner.config.option_level < required_level {
+        return normal_error(403, "Option level", "Option level too low for this spread");
+    }
+
+    let Some(quantity) = parse_price(&params, "quantity") else {
+        return normal_error(400, "Invalid quantity", "Quantity must be positive");
+    };
+    let duration = params.get("duration").cloned().unwrap_or_default();
+    let price_type = params.get("price_type").cloned().unwrap_or_default();
+    let limit_price = params.get("limit_price").and_then(|p| p.parse::<f64>().ok());
+    let net = match (
+        price_type.as_str(),
+        params.get("net_type").map(String::as_str),
+        limit_price,
+    ) {
+        ("1", _, _) => market_net,
+        ("2", Some("D"), Some(price)) if price > 0.0 => price,
+        ("2", Some("C"), Some(price)) if price > 0.0 => -price,
+        ("2", Some("E"), _) => 0.0,
+        _ => return normal_error(400, "Invalid price", "Missing or invalid net price"),
+    };
+
+    let principal = quantity * 100.0 * net;
+    if is_preview(&params) {
+        return normal(json!({
+            "result": {
+                "principal": principal,
+                "commission": 0.0,
+                "fee": 0.0,
+                "estimated_total": principal,
+                "warnings": []
+            }
+        }));
+    }
+
+    let first = &legs[0].0;
+    let order = Order {
+        order_id: String::new(),
+        symbol: first[..first.len() - 15].to_string(),
+        transaction: legs[0].1.clone(),
+        quantity,
+        price_type,
+        limit_price,
+        stop_price: None,
+        duration,
+        status: "Open".to_string(),
+        fills: Vec::new(),
+        reject_reason: None,
+        legs,
+    };
+    let order_id = place_order(&mut inner, order, market_net);
+    normal(json!({ "result": { "order_id": order_id, "result": "success" } }))
+}
+
+fn order_json(order: &Order) -> Value {
+    let executed = order.executed_quantity();
+    let avg_fill_price =
+        (executed > 0.0).then(|| order.fills.iter().map(|(q, p)| q * p).sum::<f64>() / executed);
+    let fills: Vec<Value> = order
+        .fills
+        .iter()
+        .map(|(quantity, price)| json!({ "quantity": quantity, "price": price, "time": "2025-08-06 09:31:05" }))
+        .collect();
+
+    let legs: Vec<Value> = order
+        .legs
+        .iter()
+        .map(|(symbol, transaction, ratio)| json!({ "symbol": symbol, "transaction": transaction, "ratio": ratio }))
+        .collect();
+
+    json!({
+        "order_id": order.order_id,
+        "symbol": order.symbol,
+        "transaction": order.transaction,
+        "quantity": order.quantity,
+        "executed_quantity": executed,
+        "avg_fill_price": avg_fill_price,
+        "price_type": order.price_type,
+        "limit_price": order.limit_price,
+        "stop_price": order.stop_price,
+        "duration": order.duration,
+        "status": order.status,
+        "reject_reason": order.reject_reason,
+        "create_time": "2025-08-06 09:31:02",
+        "fills": fills,
+        "legs": legs
+    })
+}
+
+async fn order_status(State(state): State<MockState>, Query(params): Query<Params>) -> Response {
+    let inner = state.lock();
+    if params.get("account") != Some(&inner.config.account) {
+        return normal_error(400, "Bad Request", "Invalid account");
+    }
+    let items: Vec<Value> = inner.orders.iter().map(order_json).collect();
+    normal(json!({ "items": items }))
+}
+
+async fn cancel_order(State(state): State<MockState>, body: Bytes) -> Response {
+    let params = parse_body(&body);
+    let mut inner = state.lock();
+    let order_id = params.get("order_id").cloned().unwrap_or_default();
+
+    let Some(order) = inner.orders.iter_mut().find(|o| o.order_id == order_id) else {
+        return normal_error(404, "Not Found", "Order not found");
+    };
+    if !matches!(order.status.as_str(), "Open" | "Partial Filled") {
+        return normal_error(400, "Order cannot be cancelled", "Order is no longer open");
+    }
+    order.status = "Cancelled".to_string();
+    normal(json!({ "result": { "order_id": order_id, "result": "success" } }))
+}
@@ -0,0 +1,318 @@
+use crate::fixtures;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// What the server asks for after username and password.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginFlow {
+    /// Authenticator app code.
+    Mfa,
+    /// One-time password sent by sms or email, optionally with the PIN fallback.
+    Otp { pin_fallback: bool },
+    /// Only the PIN fallback, no OTP recipient.
+    Pin { pin_disabled: bool },
+}
+
+#[derive(Debug, Clone)]
+pub struct OtpRecipient {
+    pub recipient_id: i64,
+    pub channel: String,
+    pub recipient_mask: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    pub username: String,
+    pub password: String,
+    pub account: String,
+    pub option_level: i64,
+    pub login_flow: LoginFlow,
+    /// Accepted MFA code, `None` accepts any 6 digits code.
+    pub mfa_code: Option<String>,
+    pub otp_code: String,
+    pub otp_recipients: Vec<OtpRecipient>,
+    pub pin: String,
+    /// Most symbols a multi-symbol OHLC request takes, `None` takes any number.
+    pub mohlc_max_symbols: Option<usize>,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            account: "12345678".to_string(),
+            option_level: 2,
+            login_flow: LoginFlow::Mfa,
+            mfa_code: Some("123456".to_string()),
+            otp_code: "654321".to_string(),
+            otp_recipients: vec![
+                OtpRecipient {
+                    recipient_id: 354687,
+                    channel: "sms".to_string(),
+                    recipient_mask: "+1******0811".to_string(),
+                },
+                OtpRecipient {
+                    recipient_id: 120129,
+                    channel: "email".to_string(),
+                    recipient_mask: "l****@g****.com".to_string(),
+                },
+            ],
+            pin: "1234".to_string(),
+            mohlc_max_symbols: None,
+        }
+    }
+}
+
+/// Injected failure, returned instead of the real response.
+#[derive(Debug, Clone)]
+pub enum Failure {
+    /// `401` with an `Unauthorized` error body.
+    Unauthorized,
+    /// Bare status code, e.g. `500` or `503`.
+    Status(u16),
+    /// `200` whose body is an error, like Firstrade does for many failures.
+    ErrorBody {
+        status_code: u16,
+        error: String,
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct WatchlistItem {
+    pub item_id: i64,
+    pub symbol: String,
+    pub sec_type: i64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Watchlist {
+    pub name: String,
+    pub is_default: bool,
+    pub items: Vec<WatchlistItem>,
+}
+
+/// An order received by the mock, as sent by the client.
+#[derive(Debug, Clone)]
+pub struct Order {
+    pub order_id: String,
+    pub symbol: String,
+    pub transaction: String,
+    pub quantity: f64,
+    pub price_type: String,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub duration: String,
+    /// Firstrade status string, e.g. `Open`, `Partial Filled`, `Filled` or `Cancelled`.
+    pub status: String,
+    /// `(quantity, price)` of each execution.
+    pub fills: Vec<(f64, f64)>,
+    pub reject_reason: Option<String>,
+    /// `(symbol, transaction, ratio)` of each leg of a spread.
+    pub legs: Vec<(String, String, u32)>,
+}
+
+impl Order {
+    pub fn executed_quantity(&self) -> f64 {
+        self.fills.iter().map(|(quantity, _)| quantity).sum()
+    }
+
+    pub(crate) fn fill(&mut self, quantity: f64, price: f64) {
+        let quantity = quantity.min(self.quantity - self.executed_quantity());
+        if quantity > 0.0 {
+            self.fills.push((quantity, price));
+        }
+        self.status = if self.executed_quantity() >= self.quantity {
+            "Filled".to_string()
+        } else {
+            "Partial Filled".to_string()
+        };
+    }
+}
+
+pub(crate) struct Inner {
+    pub config: MockConfig,
+    pub counter: u64,
+    pub t_tokens: HashSet<String>,
+    // verification sid -> t_token
+    pub verifications: HashMap<String, String>,
+    pub ftat: Option<String>,
+    pub sid: Option<String>,
+    pub failures: HashMap<String, VecDeque<Failure>>,
+    pub requests: Vec<(String, String)>,
+    pub positions: Vec<Value>,
+    pub history: Vec<Value>,
+    pub quotes: HashMap<String, Value>,
+    pub watchlists: BTreeMap<i64, Watchlist>,
+    pub orders: Vec<Order>,
+}
+
+impl Inner {
+    pub fn next_id(&mut self) -> u64 {
+        self.counter += 1;
+        self.counter
+    }
+
+    pub fn new_token(&mut self, prefix: &str) -> String {
+        let id = self.next_id();
+        format!("{prefix}{id:060X}")
+    }
+}
+
+/// Shared state of a running [`MockServer`](crate::MockServer), tests use it to change
+/// fixtures and inject failures while the server is running.
+#[derive(Clone)]
+pub struct MockState {
+    pub(crate) inner: Arc<Mutex<Inner>>,
+}
+
+impl MockState {
+    pub(crate) fn new(config: MockConfig) -> Self {
+        let mut watchlists = BTreeMap::new();
+        watchlists.insert(
+            1001,
+            Watchlist {
+                name: "Default".to_string(),
+                is_default: true,
+                items: vec![
+                    WatchlistItem {
+                        item_id: 5001,
+                        symbol: "AAPL".to_string(),
+                        sec_type: 1,
+                    },
+                    WatchlistItem {
+                        item_id: 5002,
+                        symbol: "MSFT".to_string(),
+                        sec_type: 1,
+                    },
+                ],
+            },
+        );
+
+        let inner = Inner {
+            config,
+            counter: 10_000,
+            t_tokens: HashSet::new(),
+            verifications: HashMap::new(),
+            ftat: None,
+            sid: None,
+            failures: HashMap::new(),
+            requests: Vec::new(),
+            positions: fixtures::positions(),
+            history: fixtures::history(),
+            quotes: fixtures::quotes().into_iter().collect(),
+            watchlists,
+            orders: Vec::new(),
+        };
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Fail the next request to `path` (e.g. `/private/positions`) with `failure`.
+    pub fn fail_next(&self, path: &str, failure: Failure) {
+        self.fail_times(path, failure, 1);
+    }
+
+    pub fn fail_times(&self, path: &str, failure: Failure, times: usize) {
+        let mut inner = self.lock();
+        let queue = inner.failures.entry(path.to_string()).or_default();
+        queue.extend(std::iter::repeat_n(failure, times));
+    }
+
+    /// Invalidate the current sid, private routes answer `401` until the session is renewed.
+    pub fn expire_sid(&self) {
+        self.lock().sid = None;
+    }
+
+    /// Invalidate the ftat too, so renewing the sid needs a full MFA login.
+    pub fn expire_ftat(&self) {
+        let mut inner = self.lock();
+        inner.sid = None;
+        inner.ftat = None;
+    }
+
+    pub fn sid(&self) -> Option<String> {
+        self.lock().sid.clone()
+    }
+
+    pub fn ftat(&self) -> Option<String> {
+        self.lock().ftat.clone()
+    }
+
+    /// Issue a verified session without going through login, returns `(ftat, sid)`.
+    pub fn issue_session(&self) -> (String, String) {
+        let mut inner = self.lock();
+        let ftat = inner.new_token("FTAT");
+        let sid = inner.new_token("SID");
+        inner.ftat = Some(ftat.clone());
+        inner.sid = Some(sid.clone());
+        (ftat, sid)
+    }
+
+    /// Number of requests received for `path`.
+    pub fn request_count(&self, path: &str) -> usize {
+        self.lock().requests.iter().filter(|(_, p)| p == path).count()
+    }
+
+    /// Orders placed so far, previews excluded.
+    pub fn orders(&self) -> Vec<Order> {
+        self.lock().orders.clone()
+    }
+
+    /// Execute `quantity` of an order at `price`, `false` if there is no such order.
+    pub fn fill_order(&self, order_id: &str, quantity: f64, price: f64) -> bool {
+        let mut inner = self.lock();
+        match inner.orders.iter_mut().find(|o| o.order_id == order_id) {
+            Some(order) => {
+                order.fill(quantity, price);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn reject_order(&self, order_id: &str, reason: &str) -> bool {
+        let mut inner = self.lock();
+        match inner.orders.iter_mut().find(|o| o.order_id == order_id) {
+            Some(order) => {
+                order.status = "Rejected".to_string();
+                order.reject_reason = Some(reason.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_positions(&self, positions: Vec<Value>) {
+        self.lock().positions = positions;
+    }
+
+    pub fn set_history(&self, history: Vec<Value>) {
+        self.lock().history = history;
+    }
+
+    pub fn set_quote(&self, symbol: &str, quote: Value) {
+        self.lock().quotes.insert(symbol.to_string(), quote);
+    }
+
+    pub fn remove_quote(&self, symbol: &str) {
+        self.lock().quotes.remove(symbol);
+    }
+
+    /// Id of the watchlist named `name`, `None` if there is none.
+    pub fn watchlist_id(&self, name: &str) -> Option<i64> {
+        let inner = self.lock();
+        inner
+            .watchlists
+            .iter()
+            .find(|(_, list)| list.name == name)
+            .map(|(id, _)| *id)
+    }
+}
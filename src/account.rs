@@ -1,18 +1,37 @@
-use crate::error::Result;
-use crate::models::account::{AccountHistory, AccountList, Balance, Positions, UserInfo};
+use crate::endpoint::Endpoint;
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::account::{AccountHistory, AccountList, Balance, HistoryFilter, Positions, UserInfo};
 use crate::models::company::*;
+use crate::models::option::*;
+use crate::models::order::*;
 use crate::models::quote::*;
+use crate::models::security::{SecurityType, Symbol};
 use crate::models::session::LoginVerifiedResponse;
 use crate::models::watchlist::{AddWatchListResponse, WatchListQuoteResponse, WatchListResponse};
 use crate::session::*;
+use crate::store::{CredentialStore, StoredCreds};
+use crate::stream::{HistoryStream, QuoteStream, history_stream};
+use crate::totp::MfaSecret;
 use crate::url::*;
 use crate::utils::*;
+use chrono::NaiveDate;
+use futures::{StreamExt, TryStreamExt, stream};
 use reqwest::Client as HttpClient;
-use std::collections::HashMap;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::{Mutex, RwLock};
 use typed_builder::TypedBuilder;
 
+// Requests in flight for one multi-symbol call
+const MOHLC_CONCURRENCY: usize = 4;
+const DEFAULT_QUOTE_CONCURRENCY: usize = 8;
+// Largest page the positions endpoint serves
+const POSITIONS_PER_PAGE: u32 = 200;
+
 #[derive(Clone, TypedBuilder)]
 pub struct FtAccountConfig {
     username: String,
@@ -21,6 +40,12 @@ pub struct FtAccountConfig {
     sid: String,
     account_id: String,
     client: Option<HttpClient>,
+    /// Base32 authenticator secret, enables [`FtAccount::re_login_with_totp`].
+    #[builder(default, setter(strip_option))]
+    mfa_secret: Option<String>,
+    /// API host and client identity, defaults to the production Firstrade API.
+    #[builder(default)]
+    endpoint: Endpoint,
 }
 
 impl From<FtAccountConfig> for FtCreds {
@@ -30,15 +55,40 @@ impl From<FtAccountConfig> for FtCreds {
             password: FirstTradePassword::from(builder.password),
             ftat: FirstTradeAccessToken::from(builder.ftat),
             sid: SessionId::from(builder.sid),
+            mfa_secret: builder.mfa_secret.map(MfaSecret::from),
         }
     }
 }
 
+/// Asked for a fresh MFA code when the session cannot be renewed without one.
+pub type MfaCodeCallback =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<String>> + Send>> + Send + Sync>;
+
+/// What [`FtAccount`] does when a request is rejected as `ErrorKind::Unauthorized`.
+#[derive(Clone, Default)]
+pub enum RefreshPolicy {
+    /// Return the error to the caller.
+    #[default]
+    Disabled,
+    /// Renew the sid and retry once, falling back to a TOTP re-login when the credentials
+    /// carry an MFA secret.
+    RenewSid,
+    /// Same as `RenewSid`, but ask the callback for an MFA code when there is no MFA secret.
+    RenewSidOrCallback(MfaCodeCallback),
+}
+
 #[derive(Clone)]
 pub struct FtAccount {
     client: HttpClient,
+    endpoint: Endpoint,
     account_id: AccountId,
     cred: Arc<RwLock<FtCreds>>,
+    refresh_policy: RefreshPolicy,
+    credential_store: Option<Arc<dyn CredentialStore>>,
+    quote_concurrency: usize,
+    mohlc_chunk_size: usize,
+    // NOTE: held while refreshing, so concurrent requests sharing `cred` only renew once
+    refresh_lock: Arc<Mutex<()>>,
 }
 
 impl FtAccount {
@@ -46,59 +96,148 @@ impl FtAccount {
         let client = if let Some(client) = acct_config.client.take() {
             client
         } else {
-            build_default_https_client()
+            build_default_https_client(&acct_config.endpoint)
         };
+        let endpoint = acct_config.endpoint.clone();
         let account_id = acct_config.account_id.clone().into();
         let ft_creds: FtCreds = acct_config.into();
         let cred = Arc::new(RwLock::new(ft_creds));
 
         Self {
             client,
+            endpoint,
             account_id,
             cred,
+            refresh_policy: RefreshPolicy::default(),
+            credential_store: None,
+            quote_concurrency: DEFAULT_QUOTE_CONCURRENCY,
+            mohlc_chunk_size: DEFAULT_MOHLC_CHUNK_SIZE,
+            refresh_lock: Arc::new(Mutex::new(())),
         }
     }
 
     pub fn from_session(session: FtSession, account_id: String) -> Self {
+        let credential_store = session.credential_store();
+        let endpoint = session.endpoint().clone();
         if let Some(ft_creds) = session.ft_creds {
             let cred = FtCreds {
                 username: ft_creds.username,
                 password: ft_creds.password,
                 ftat: ft_creds.ftat,
                 sid: ft_creds.sid,
+                mfa_secret: ft_creds.mfa_secret,
             };
             let cred = Arc::new(RwLock::new(cred));
 
             Self {
                 client: session.client.clone(),
+                endpoint,
                 account_id: account_id.into(),
                 cred,
+                refresh_policy: RefreshPolicy::default(),
+                credential_store,
+                quote_concurrency: DEFAULT_QUOTE_CONCURRENCY,
+                mohlc_chunk_size: DEFAULT_MOHLC_CHUNK_SIZE,
+                refresh_lock: Arc::new(Mutex::new(())),
             }
         } else {
             panic!("Session does not contain FirstTrade credentials");
         }
     }
 
+    /// Resume from the credentials saved in `store`, which also receives later saves as with
+    /// [`FtAccount::with_credential_store`].
+    ///
+    /// Fails with `ErrorKind::ConfigInvalid` when nothing has been saved yet. Without a stored
+    /// password (see [`FileCredentialStore::with_login`](crate::store::FileCredentialStore::with_login))
+    /// renewing the sid or logging in again fails with `ErrorKind::ConfigInvalid`.
+    pub fn from_store(
+        store: Arc<dyn CredentialStore>,
+        account_id: String,
+        endpoint: Endpoint,
+    ) -> Result<Self> {
+        let stored = store
+            .load()?
+            .ok_or_else(|| Error::new(ErrorKind::ConfigInvalid, "no credentials in the credential store"))?;
+        let config = FtAccountConfig::builder()
+            .username(stored.username.clone().unwrap_or_default())
+            .password(stored.password.clone().unwrap_or_default())
+            .ftat(stored.ftat.clone())
+            .sid(stored.sid.clone())
+            .account_id(account_id)
+            .client(None)
+            .endpoint(endpoint)
+            .build();
+        Ok(Self::new(config).with_credential_store(store))
+    }
+
+    pub fn with_refresh_policy(mut self, refresh_policy: RefreshPolicy) -> Self {
+        self.refresh_policy = refresh_policy;
+        self
+    }
+
+    /// Most quote requests [`FtAccount::get_quotes`] keeps in flight, 8 by default.
+    pub fn with_quote_concurrency(mut self, concurrency: usize) -> Self {
+        self.quote_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Most symbols [`FtAccount::get_stocks_mohlc`] sends per request, [`DEFAULT_MOHLC_CHUNK_SIZE`]
+    /// by default.
+    pub fn with_mohlc_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.mohlc_chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Save the credentials to `store` whenever the sid is renewed or the account logs in again.
+    pub fn with_credential_store(mut self, store: Arc<dyn CredentialStore>) -> Self {
+        self.credential_store = Some(store);
+        self
+    }
+
+    /// Save the current credentials to the configured [`CredentialStore`], if any.
+    pub async fn save_creds(&self) -> Result<()> {
+        if let Some(store) = &self.credential_store {
+            let cred = self.cred.read().await;
+            store.save(&StoredCreds::from(&*cred))?;
+        }
+        Ok(())
+    }
+
+    async fn save_creds_or_warn(&self) {
+        if let Err(err) = self.save_creds().await {
+            log::warn!("failed to save credentials: {err}");
+        }
+    }
+
     // NOTE: use it when refreshing sid fail, will need mfa or otp to login again
     pub async fn re_login(&self, mfa_code: String) -> Result<FtCreds> {
-        let new_ftat;
-        let new_sid;
-        {
-            let old_cred = self.cred.read().await;
-            let mut ft_config = FtSessionConfig::default();
-            ft_config.set_username(String::from(old_cred.username.as_str()));
-            ft_config.set_password(String::from(old_cred.password.as_str()));
-            ft_config.set_mfa_code(mfa_code);
-
-            let builder = FtSessionBuilder::new(ft_config)?;
-            let mut session = FtSession::from_builder(builder);
-            session.login().await?;
-            let temp = session.ft_creds.unwrap();
-            new_ftat = temp.ftat.as_string();
-            new_sid = temp.sid.as_string();
-        }
-        self.set_new_sid(new_ftat).await?;
-        self.set_new_ftat(new_sid).await?;
+        let mut ft_config = FtSessionConfig::from_creds(&*self.cred.read().await, self.endpoint.clone());
+        ft_config.set_mfa_code(mfa_code);
+        self.re_login_with_config(ft_config).await
+    }
+
+    /// Re-login with a code generated from the MFA secret, no human needed.
+    pub async fn re_login_with_totp(&self) -> Result<FtCreds> {
+        let ft_config = {
+            let cred = self.cred.read().await;
+            if cred.mfa_secret.is_none() {
+                return Err(login_credential_error("mfa_secret"));
+            }
+            FtSessionConfig::from_creds(&cred, self.endpoint.clone())
+        };
+        self.re_login_with_config(ft_config).await
+    }
+
+    async fn re_login_with_config(&self, ft_config: FtSessionConfig) -> Result<FtCreds> {
+        let builder = FtSessionBuilder::new(ft_config)?;
+        let mut session = FtSession::from_builder(builder);
+        session.login().await?;
+        let temp = session.ft_creds.unwrap();
+
+        self.set_new_ftat(temp.ftat.as_string()).await?;
+        self.set_new_sid(temp.sid.as_string()).await?;
+        self.save_creds_or_warn().await;
         let new_cred = self.cred.read().await.clone();
         Ok(new_cred)
     }
@@ -107,13 +246,18 @@ impl FtAccount {
         let sid;
         {
             let cred = self.cred.read().await;
+            if cred.password.as_str().is_empty() {
+                return Err(missing_password_error());
+            }
             let mut body = HashMap::new();
             body.insert("username", cred.username.as_str());
             body.insert("password", cred.password.as_str());
-            let resp: LoginVerifiedResponse = post_with_auth(&self.client, login(), &body, &cred).await?;
+            let resp: LoginVerifiedResponse =
+                post_with_auth(&self.client, login(self.host()), &body, &cred).await?;
             sid = resp.sid;
         }
         self.set_new_sid(sid.clone()).await?;
+        self.save_creds_or_warn().await;
         Ok(sid)
     }
 
@@ -129,136 +273,541 @@ impl FtAccount {
         Ok(())
     }
 
+    #[inline]
+    fn host(&self) -> &str {
+        self.endpoint.base_url()
+    }
+
     pub async fn get_sid(&self) -> String {
         let cred = self.cred.read().await;
         cred.sid.as_string()
     }
+
+    /// Refresh the credentials after `stale_sid` was rejected.
+    ///
+    /// Only one task refreshes at a time, the others wait and reuse its result.
+    async fn refresh_creds(&self, stale_sid: &str) -> Result<()> {
+        let _guard = self.refresh_lock.lock().await;
+        if self.cred.read().await.sid.as_str() != stale_sid {
+            return Ok(());
+        }
+
+        let err = match self.renew_sid().await {
+            Ok(_) => {
+                log::info!("session renewed");
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+        log::warn!("failed to renew session: {err}");
+        if err.kind() == ErrorKind::ConfigInvalid {
+            return Err(err);
+        }
+
+        if self.cred.read().await.mfa_secret.is_some() {
+            self.re_login_with_totp().await?;
+            return Ok(());
+        }
+        match &self.refresh_policy {
+            RefreshPolicy::RenewSidOrCallback(callback) => {
+                let mfa_code = callback().await?;
+                self.re_login(mfa_code).await?;
+                Ok(())
+            }
+            _ => Err(err),
+        }
+    }
+
+    async fn with_refresh<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(FtCreds) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let cred = self.cred.read().await.clone();
+        let sid = cred.sid.as_string();
+
+        match request(cred).await {
+            Err(err)
+                if err.kind() == ErrorKind::Unauthorized
+                    && !matches!(self.refresh_policy, RefreshPolicy::Disabled) =>
+            {
+                self.refresh_creds(&sid).await?;
+                let cred = self.cred.read().await.clone();
+                request(cred).await
+            }
+            res => res,
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, url: String) -> Result<T> {
+        self.with_refresh(|cred| {
+            let url = url.clone();
+            async move { get_with_auth(&self.client, url, &cred).await }
+        })
+        .await
+    }
+
+    async fn post<T: DeserializeOwned>(&self, url: String, body: &HashMap<&str, &str>) -> Result<T> {
+        self.with_refresh(|cred| {
+            let url = url.clone();
+            async move { post_with_auth(&self.client, url, body, &cred).await }
+        })
+        .await
+    }
+
+    async fn delete<T: DeserializeOwned>(&self, url: String) -> Result<T> {
+        self.with_refresh(|cred| {
+            let url = url.clone();
+            async move { delete_with_auth(&self.client, url, &cred).await }
+        })
+        .await
+    }
 }
 
 impl FtAccount {
     pub async fn get_market_time(&self) -> Result<MarketTimeResponse> {
-        let url = market_time();
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = market_time(self.host());
+        self.get(url).await
     }
 
     pub async fn get_account_list(&self) -> Result<AccountList> {
-        let url = account_list();
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = account_list(self.host());
+        self.get(url).await
     }
 
     pub async fn get_user_info(&self) -> Result<UserInfo> {
-        let url = user_info(self.account_id.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = user_info(self.host(), self.account_id.as_str());
+        self.get(url).await
     }
 
+    /// Every position of the account, merged from all pages with the totals recomputed.
     pub async fn get_account_positions(&self) -> Result<Positions> {
-        let url = account_positions(self.account_id.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let first = self.get_account_positions_page(1, POSITIONS_PER_PAGE).await?;
+        let mut pages = Vec::new();
+        for page in 2..=first.pages {
+            let positions = self.get_account_positions_page(page, POSITIONS_PER_PAGE).await?;
+            if positions.items.is_empty() {
+                break;
+            }
+            pages.push(positions);
+        }
+
+        let total = first.total;
+        let positions = first.merge(pages);
+        if positions.total != total {
+            log::warn!(
+                "positions changed while paging: expected {total}, got {}",
+                positions.total
+            );
+        }
+        Ok(positions)
+    }
+
+    /// One page of positions as returned by the server, for loading them incrementally.
+    pub async fn get_account_positions_page(&self, page: u32, per_page: u32) -> Result<Positions> {
+        let url = account_positions(
+            self.host(),
+            self.account_id.as_str(),
+            page.max(1),
+            per_page.max(1),
+        );
+        self.get(url).await
     }
 
     pub async fn get_account_balances(&self) -> Result<Balance> {
-        let url = account_balances(self.account_id.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = account_balances(self.host(), self.account_id.as_str());
+        self.get(url).await
     }
 
     pub async fn get_account_history(&self, range: &str, page: u32, per_page: u32) -> Result<AccountHistory> {
-        let url = account_history(self.account_id.as_str(), range, page, per_page);
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = account_history(self.host(), self.account_id.as_str(), range, page, per_page);
+        self.get(url).await
+    }
+
+    /// Every history item of `range` across all pages, fetched lazily page by page.
+    pub fn history_stream(&self, range: &str, filter: HistoryFilter) -> HistoryStream {
+        history_stream(self.clone(), range, filter)
     }
 
     pub async fn get_fundamental(&self, symbol: String) -> Result<FundamentalResponse> {
-        let url = fundamental(symbol.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = fundamental(self.host(), symbol.as_str());
+        self.get(url).await
     }
 
     pub async fn get_company_profile(&self, symbol: String) -> Result<CompanyProfileResponse> {
-        let url = company_profile(symbol.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = company_profile(self.host(), symbol.as_str());
+        self.get(url).await
     }
 
     pub async fn get_cash_dividend(&self, symbol: String) -> Result<CashDividendResponse> {
-        let url = cash_dividend(symbol.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = cash_dividend(self.host(), symbol.as_str());
+        self.get(url).await
     }
 
     pub async fn get_corp_calendar(&self, symbol: String) -> Result<CorpCalendarResponse> {
-        let url = corp_calendar(symbol.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = corp_calendar(self.host(), symbol.as_str());
+        self.get(url).await
     }
 
     pub async fn get_single_quote(&self, symbol: String) -> Result<SingleQuoteResponse> {
-        let url = single_quote(self.account_id.as_str(), symbol.as_str());
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = single_quote(self.host(), self.account_id.as_str(), symbol.as_str());
+        self.get(url).await
     }
 
-    pub async fn get_stock_ohlc(&self, symbols: String, range: String) -> Result<OhlcResponse> {
-        let url = stock_ohlc(symbols.as_str(), &range);
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+    /// Quote of one symbol, `ErrorKind::NotFound` if the server doesn't know it.
+    pub async fn get_quote(&self, symbol: &Symbol) -> Result<QuoteResult> {
+        let resp = self.get_single_quote(symbol.to_string()).await?;
+        match resp.result {
+            Some(quote) if resp.error.is_empty() => Ok(quote),
+            _ => Err(Error::new(ErrorKind::NotFound, "quote not found")
+                .with_context("symbol", symbol.as_str())
+                .with_context("error", resp.error)
+                .with_context("message", resp.message)),
+        }
     }
 
-    pub async fn get_stocks_mohlc(&self, symbols: String, resolution: u8) -> Result<MohlcResponse> {
-        let url = stocks_mohlc(symbols.as_str(), resolution);
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+    /// Quotes of many symbols, fetched concurrently, see [`FtAccount::with_quote_concurrency`].
+    ///
+    /// A failed symbol only fails its own entry, duplicates are requested once.
+    pub async fn get_quotes(&self, symbols: &[Symbol]) -> QuoteBatch {
+        let started = Instant::now();
+        let mut seen = HashSet::new();
+        let symbols: Vec<Symbol> = symbols.iter().filter(|s| seen.insert(*s)).cloned().collect();
+
+        let requests = symbols.into_iter().enumerate().map(|(i, symbol)| async move {
+            let started = Instant::now();
+            let result = self.get_quote(&symbol).await;
+            (i, symbol, result, started.elapsed())
+        });
+        let mut quotes: Vec<_> = stream::iter(requests)
+            .buffer_unordered(self.quote_concurrency)
+            .collect()
+            .await;
+        quotes.sort_by_key(|(i, ..)| *i);
+
+        let quotes = quotes
+            .into_iter()
+            .map(|(_, symbol, result, latency)| (symbol, result, latency))
+            .collect();
+        QuoteBatch::new(quotes, started.elapsed())
+    }
+
+    /// Poll quotes of `symbols` every `interval`, yielding only new and changed quotes.
+    ///
+    /// All symbols are quoted with one watchlist request per poll, see [`QuoteStream`].
+    pub fn quote_stream(
+        &self,
+        symbols: impl IntoIterator<Item = Symbol>,
+        interval: std::time::Duration,
+    ) -> QuoteStream {
+        QuoteStream::new(self.clone(), symbols, interval)
+    }
+
+    pub async fn get_stock_ohlc(&self, symbols: String, range: OhlcRange) -> Result<OhlcResponse> {
+        let url = stock_ohlc(self.host(), symbols.as_str(), range.as_str());
+        self.get(url).await
+    }
+
+    /// OHLC bars of several symbols, keyed by symbol.
+    ///
+    /// Lists are split into chunks fetched concurrently, see [`FtAccount::with_mohlc_chunk_size`],
+    /// symbols the server doesn't know are left out of the result.
+    pub async fn get_stocks_mohlc(
+        &self,
+        symbols: &[Symbol],
+        resolution: Resolution,
+    ) -> Result<HashMap<String, StockOhlc>> {
+        let mut symbols = symbols.to_vec();
+        symbols.sort();
+        symbols.dedup();
+
+        let chunks = symbols.chunks(self.mohlc_chunk_size).map(|chunk| async move {
+            let resp: MohlcResponse = self
+                .get(stocks_mohlc(self.host(), chunk, resolution.get()))
+                .await?;
+            match resp.result {
+                Some(result) if resp.error.is_empty() => Ok(result),
+                _ => Err(Error::new(ErrorKind::Unexpected, "ohlc request failed")
+                    .with_context("error", resp.error)
+                    .with_context("message", resp.message)
+                    .with_context(
+                        "symbols",
+                        chunk.iter().map(Symbol::as_str).collect::<Vec<_>>().join(","),
+                    )),
+            }
+        });
+
+        stream::iter(chunks)
+            .buffer_unordered(MOHLC_CONCURRENCY)
+            .try_fold(HashMap::new(), |mut merged, result| async move {
+                merged.extend(result);
+                Ok(merged)
+            })
+            .await
     }
 
     pub async fn get_all_watchlists(&self) -> Result<WatchListResponse> {
-        let url = watchlists();
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = watchlists(self.host());
+        self.get(url).await
     }
 
     pub async fn get_watchlist_quote(&self, id: u32) -> Result<WatchListQuoteResponse> {
-        let url = watchlist_quote(id);
-        let cred = self.cred.read().await;
-        get_with_auth(&self.client, url, &cred).await
+        let url = watchlist_quote(self.host(), id);
+        self.get(url).await
     }
 
     pub async fn add_new_watchlist(&self, name: String) -> Result<AddWatchListResponse> {
-        let url = watchlists();
-        let cred = self.cred.read().await;
+        let url = watchlists(self.host());
         let body = HashMap::from([("name", name.as_str())]);
-        post_with_auth(&self.client, url, &body, &cred).await
+        self.post(url, &body).await
     }
 
     pub async fn watchlist_add_symbol(
         &self,
         watchlist_id: u32,
         symbol: String,
-        sec_type: u8,
+        sec_type: SecurityType,
     ) -> Result<AddWatchListResponse> {
-        let url = format!("{}/{}", watchlist(), watchlist_id);
-        let cred = self.cred.read().await;
+        let url = format!("{}/{}", watchlist(self.host()), watchlist_id);
 
         let mut body = HashMap::new();
         let sec_type = sec_type.to_string();
         body.insert("symbol", symbol.as_str());
         body.insert("sec_type", sec_type.as_str());
 
-        post_with_auth(&self.client, url, &body, &cred).await
+        self.post(url, &body).await
     }
 
     pub async fn watchlist_remove_symbol(&self, symbol_id: u32) -> Result<AddWatchListResponse> {
-        let url = format!("{}/{}", watchlist(), symbol_id);
-        let cred = self.cred.read().await;
-        delete_with_auth(&self.client, url, &cred).await
+        let url = format!("{}/{}", watchlist(self.host()), symbol_id);
+        self.delete(url).await
     }
 
     pub async fn delete_watchlist(&self, watchlist_id: u32) -> Result<AddWatchListResponse> {
-        let url = format!("{}/{}", watchlists(), watchlist_id);
-        let cred = self.cred.read().await;
-        delete_with_auth(&self.client, url, &cred).await
+        let url = format!("{}/{}", watchlists(self.host()), watchlist_id);
+        self.delete(url).await
+    }
+}
+
+impl FtAccount {
+    /// Expiration dates listed for options on `symbol`, nearest first.
+    pub async fn get_option_expirations(&self, symbol: &str) -> Result<Vec<OptionExpiration>> {
+        let symbol = symbol.trim().to_uppercase();
+        let resp: OptionExpirationsResponse = self.get(option_expirations(self.host(), &symbol)).await?;
+        match resp.items {
+            Some(items) if resp.error.is_empty() => Ok(items),
+            _ => Err(option_chain_error(&resp.error, &resp.message).with_context("symbol", symbol)),
+        }
+    }
+
+    /// Calls and puts of `symbol` expiring on `expiration`.
+    ///
+    /// Greeks come from a separate request, if it fails the chain is still returned without them.
+    pub async fn get_option_chain(&self, symbol: &str, expiration: NaiveDate) -> Result<OptionChain> {
+        let symbol = symbol.trim().to_uppercase();
+        let exp_date = expiration.format("%Y%m%d").to_string();
+        let resp: OptionChainResponse = self.get(option_chain(self.host(), &symbol, &exp_date)).await?;
+        let mut strikes = match resp.items {
+            Some(items) if resp.error.is_empty() => items,
+            _ => {
+                return Err(option_chain_error(&resp.error, &resp.message)
+                    .with_context("symbol", symbol)
+                    .with_context("expiration", exp_date));
+            }
+        };
+
+        match self.get_option_greeks(&symbol, &exp_date).await {
+            Ok(greeks) => {
+                for strike in strikes.iter_mut() {
+                    for contract in [strike.call.as_mut(), strike.put.as_mut()].into_iter().flatten() {
+                        contract.greeks = greeks.get(&contract.symbol).copied();
+                    }
+                }
+            }
+            Err(err) => log::warn!("failed to get greeks of {symbol} {exp_date}: {err}"),
+        }
+
+        strikes.sort_by_key(|strike| strike.strike_price);
+        Ok(OptionChain {
+            symbol,
+            expiration,
+            strikes,
+        })
+    }
+
+    async fn get_option_greeks(&self, symbol: &str, exp_date: &str) -> Result<HashMap<OccSymbol, Greeks>> {
+        let body = HashMap::from([
+            ("type", "chain"),
+            ("chains_range", "A"),
+            ("root_symbol", symbol),
+            ("exp_date", exp_date),
+        ]);
+        let resp: GreeksResponse = self.post(option_greeks(self.host()), &body).await?;
+        match resp.result {
+            Some(result) if resp.error.is_empty() => Ok(result
+                .into_iter()
+                .map(|item| (item.symbol, item.greeks))
+                .collect()),
+            _ => Err(option_chain_error(&resp.error, &resp.message)),
+        }
+    }
+}
+
+// NOTE: orders are always previewed first, `place_*` only accepts an `OrderPreview`
+impl FtAccount {
+    /// Preview `order` without sending it, returning its estimated cost and commission.
+    pub async fn preview_stock_order(&self, order: StockOrder) -> Result<OrderPreview<StockOrder>> {
+        order.validate()?;
+        let form = order.to_form(self.account_id.as_str(), true);
+        let resp: PreviewResponse = self.post(stock_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(estimate) if resp.error.is_empty() => Ok(OrderPreview::new(order, estimate)),
+            _ => Err(order_rejected_error(&resp.error, &resp.message).with_context("symbol", &order.symbol)),
+        }
+    }
+
+    /// Send an order accepted by [`FtAccount::preview_stock_order`].
+    pub async fn place_stock_order(&self, preview: OrderPreview<StockOrder>) -> Result<OrderConfirmation> {
+        let order = preview.order();
+        let form = order.to_form(self.account_id.as_str(), false);
+        let resp: PlaceOrderResponse = self.post(stock_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(confirmation) if resp.error.is_empty() => Ok(confirmation),
+            _ => Err(order_rejected_error(&resp.error, &resp.message).with_context("symbol", &order.symbol)),
+        }
+    }
+
+    /// Open and recent orders of the account.
+    pub async fn list_orders(&self) -> Result<OrderListResponse> {
+        let url = order_status(self.host(), self.account_id.as_str());
+        self.get(url).await
+    }
+
+    /// Status and fills of a single order, `ErrorKind::NotFound` if the account has no such order.
+    pub async fn get_order(&self, order_id: &str) -> Result<OrderStatus> {
+        let resp = self.list_orders().await?;
+        resp.items
+            .into_iter()
+            .flatten()
+            .find(|order| order.order_id == order_id)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, "order not found").with_context("order_id", order_id)
+            })
+    }
+
+    pub async fn cancel_order(&self, order_id: &str) -> Result<OrderConfirmation> {
+        let body = HashMap::from([("order_id", order_id)]);
+        let resp: CancelOrderResponse = self.post(cancel_order(self.host()), &body).await?;
+        match resp.result {
+            Some(confirmation) if resp.error.is_empty() => Ok(confirmation),
+            _ => Err(Error::new(ErrorKind::OrderRejected, "cancel rejected")
+                .with_context("order_id", order_id)
+                .with_context("error", resp.error)
+                .with_context("message", resp.message)),
+        }
+    }
+
+    /// Preview replacing the open order `order_id` with `change` applied.
+    pub async fn preview_replace_order(
+        &self,
+        order_id: &str,
+        change: OrderChange,
+    ) -> Result<OrderPreview<ReplaceOrder>> {
+        let original = self.get_order(order_id).await?;
+        let replace = ReplaceOrder::new(&original, change)?;
+        let form = replace.to_form(self.account_id.as_str(), true);
+        let resp: PreviewResponse = self.post(replace_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(estimate) if resp.error.is_empty() => Ok(OrderPreview::new(replace, estimate)),
+            _ => Err(order_rejected_error(&resp.error, &resp.message).with_context("order_id", order_id)),
+        }
+    }
+
+    /// Replace an open order in a single request, it is never both cancelled and placed again.
+    pub async fn replace_order(&self, preview: OrderPreview<ReplaceOrder>) -> Result<ReplaceConfirmation> {
+        let replace = preview.order();
+        let form = replace.to_form(self.account_id.as_str(), false);
+        let resp: ReplaceOrderResponse = self.post(replace_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(confirmation) if resp.error.is_empty() => Ok(confirmation),
+            _ => Err(order_rejected_error(&resp.error, &resp.message)
+                .with_context("order_id", &replace.original_order_id)),
+        }
+    }
+
+    /// Preview `order` without sending it, after checking the account's option level allows it.
+    ///
+    /// Sales to open are not checked, see [`OptionAction::required_option_level`].
+    pub async fn preview_option_order(&self, order: OptionOrder) -> Result<OrderPreview<OptionOrder>> {
+        order.validate()?;
+        if let Some(required_level) = order.action.required_option_level() {
+            self.check_option_level(required_level)
+                .await
+                .map_err(|e| e.with_context("symbol", &order.symbol))?;
+        }
+
+        let form = order.to_form(self.account_id.as_str(), true);
+        let resp: PreviewResponse = self.post(option_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(estimate) if resp.error.is_empty() => Ok(OrderPreview::new(order, estimate)),
+            _ => Err(order_rejected_error(&resp.error, &resp.message).with_context("symbol", &order.symbol)),
+        }
+    }
+
+    /// Send an order accepted by [`FtAccount::preview_option_order`].
+    pub async fn place_option_order(&self, preview: OrderPreview<OptionOrder>) -> Result<OrderConfirmation> {
+        let order = preview.order();
+        let form = order.to_form(self.account_id.as_str(), false);
+        let resp: PlaceOrderResponse = self.post(option_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(confirmation) if resp.error.is_empty() => Ok(confirmation),
+            _ => Err(order_rejected_error(&resp.error, &resp.message).with_context("symbol", &order.symbol)),
+        }
+    }
+
+    /// Preview a spread without sending it, after checking the account's option level allows it.
+    pub async fn preview_multi_leg_order(&self, order: MultiLegOrder) -> Result<OrderPreview<MultiLegOrder>> {
+        order.validate()?;
+        self.check_option_level(order.required_option_level()).await?;
+
+        let form = order.to_form(self.account_id.as_str(), true);
+        let resp: PreviewResponse = self.post(multi_leg_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(estimate) if resp.error.is_empty() => Ok(OrderPreview::new(order, estimate)),
+            _ => Err(order_rejected_error(&resp.error, &resp.message)),
+        }
+    }
+
+    /// Send a spread accepted by [`FtAccount::preview_multi_leg_order`], all legs in one order.
+    pub async fn place_multi_leg_order(
+        &self,
+        preview: OrderPreview<MultiLegOrder>,
+    ) -> Result<OrderConfirmation> {
+        let form = preview.order().to_form(self.account_id.as_str(), false);
+        let resp: PlaceOrderResponse = self.post(multi_leg_order(self.host()), &form_body(&form)).await?;
+        match resp.result {
+            Some(confirmation) if resp.error.is_empty() => Ok(confirmation),
+            _ => Err(order_rejected_error(&resp.error, &resp.message)),
+        }
+    }
+
+    async fn check_option_level(&self, required_level: i64) -> Result<()> {
+        let accounts = self.get_account_list().await?;
+        let account = accounts
+            .items
+            .into_iter()
+            .flatten()
+            .find(|item| item.account == self.account_id.as_str())
+            .ok_or_else(|| {
+                Error::new(ErrorKind::NotFound, "account not found")
+                    .with_context("account", self.account_id.as_str())
+            })?;
+
+        if account.option_level < required_level {
+            return Err(Error::new(ErrorKind::OrderRejected, "option level too low")
+                .with_context("option_level", account.option_level.to_string())
+                .with_context("required_level", required_level.to_string()));
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,124 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::utils::parse_request_header_error;
+use http::HeaderValue;
+use std::fmt::Debug;
+
+pub(crate) const FT_HOST: &str = "https://api3x.firstrade.com";
+pub(crate) static ACCESS_TOKEN: &str = "833w3XuIFycv18ybi";
+pub(crate) static USER_AGENT: &str = "okhttp/4.9.2";
+
+/// Where requests are sent and which app the client impersonates.
+///
+/// Defaults to the production Firstrade API, override it to point at a mock server or a
+/// recording proxy, or to follow Firstrade when it rotates the app version.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    base_url: String,
+    access_token: String,
+    user_agent: String,
+}
+
+impl Debug for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("base_url", &self.base_url)
+            .field("access_token", &"***")
+            .field("user_agent", &self.user_agent)
+            .finish()
+    }
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Self {
+            base_url: FT_HOST.to_string(),
+            access_token: ACCESS_TOKEN.to_string(),
+            user_agent: USER_AGENT.to_string(),
+        }
+    }
+}
+
+impl Endpoint {
+    /// Use another base url with the default access token and user agent.
+    pub fn new(base_url: &str) -> Result<Self> {
+        let mut endpoint = Self::default();
+        endpoint.set_base_url(base_url)?;
+        Ok(endpoint)
+    }
+
+    pub fn set_base_url(&mut self, base_url: &str) -> Result<&mut Self> {
+        let url = reqwest::Url::parse(base_url).map_err(|e| {
+            Error::new(ErrorKind::ConfigInvalid, "invalid base url")
+                .with_context("base_url", base_url)
+                .set_source(e)
+        })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(
+                Error::new(ErrorKind::ConfigInvalid, "base url must be http or https")
+                    .with_context("base_url", base_url),
+            );
+        }
+
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        Ok(self)
+    }
+
+    pub fn set_access_token(&mut self, access_token: String) -> Result<&mut Self> {
+        HeaderValue::from_str(&access_token).map_err(parse_request_header_error)?;
+        self.access_token = access_token;
+        Ok(self)
+    }
+
+    pub fn set_user_agent(&mut self, user_agent: String) -> Result<&mut Self> {
+        HeaderValue::from_str(&user_agent).map_err(parse_request_header_error)?;
+        self.user_agent = user_agent;
+        Ok(self)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    pub fn user_agent(&self) -> &str {
+        &self.user_agent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_base_url() {
+        assert_eq!(Endpoint::default().base_url(), FT_HOST);
+
+        let endpoint = Endpoint::new("http://127.0.0.1:8080/").unwrap();
+        assert_eq!(endpoint.base_url(), "http://127.0.0.1:8080");
+        assert_eq!(endpoint.access_token(), ACCESS_TOKEN);
+
+        let err = Endpoint::new("127.0.0.1:8080").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+        let err = Endpoint::new("ftp://127.0.0.1").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+    }
+
+    #[test]
+    fn test_endpoint_identity() {
+        let mut endpoint = Endpoint::default();
+        endpoint
+            .set_user_agent("okhttp/4.12.0".to_string())
+            .unwrap()
+            .set_access_token("token".to_string())
+            .unwrap();
+        assert_eq!(endpoint.user_agent(), "okhttp/4.12.0");
+        assert_eq!(endpoint.access_token(), "token");
+
+        let err = endpoint.set_user_agent("bad\nagent".to_string()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+        assert_eq!(endpoint.user_agent(), "okhttp/4.12.0");
+    }
+}
@@ -22,6 +22,8 @@ pub enum ErrorKind {
     LoginFailed,
     RateLimited,
     ConditionNotMatch,
+    /// The server refused an order, e.g. insufficient buying power or an invalid price.
+    OrderRejected,
 }
 
 impl ErrorKind {
@@ -57,6 +59,7 @@ impl From<ErrorKind> for &'static str {
             ErrorKind::LoginFailed => "LoginFailed",
             ErrorKind::RateLimited => "RateLimited",
             ErrorKind::ConditionNotMatch => "ConditionNotMatch",
+            ErrorKind::OrderRejected => "OrderRejected",
         }
     }
 }
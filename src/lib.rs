@@ -1,8 +1,16 @@
 #![recursion_limit = "256"]
+#![allow(clippy::result_large_err)]
 
 pub mod account;
+pub mod endpoint;
 pub mod error;
 pub mod models;
 pub mod session;
+pub mod store;
+pub mod stream;
+pub(crate) mod totp;
 pub(crate) mod url;
 pub(crate) mod utils;
+
+#[cfg(feature = "decimal")]
+pub use rust_decimal;
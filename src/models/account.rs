@@ -1,7 +1,12 @@
-use chrono::{DateTime, Utc};
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::money::{Money, to_f64};
+use crate::models::option::OccSymbol;
+use crate::models::security::SecurityType;
+use chrono::NaiveDate;
 use http::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use typed_builder::TypedBuilder;
 
 // ==================== Account List ====================
 #[derive(Serialize, Deserialize)]
@@ -12,7 +17,7 @@ pub struct AccountList {
     pub error: String,
     pub message: String,
     pub items: Option<Vec<AccountItems>>,
-    pub grand_total: Option<f64>,
+    pub grand_total: Option<Money>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -24,7 +29,7 @@ pub struct AccountItems {
     pub r#type: String,
     pub ext_hours_trading_status: String,
     pub signed_fractional: String,
-    pub total_value: f64,
+    pub total_value: Money,
     pub option_level: i64,
     pub default: bool,
 }
@@ -78,11 +83,12 @@ pub struct Positions {
     pub per_page: u32,
     pub total: u32,
     pub realtime: String,
+    #[serde(deserialize_with = "deserialize_position_items")]
     pub items: Vec<PositionItem>,
-    pub total_market_value: f64,
-    pub total_gainloss: f64,
+    pub total_market_value: Money,
+    pub total_gainloss: Money,
     pub total_gainloss_percent: f64,
-    pub total_daychange_amount: f64,
+    pub total_daychange_amount: Money,
     pub total_daychange_percent: f64,
     #[serde(rename = "isCostBasisReady")]
     pub is_cost_basis_ready: bool,
@@ -90,27 +96,63 @@ pub struct Positions {
     pub pagination: Value,
 }
 
+impl Positions {
+    // NOTE: the server totals may only cover their own page, so they are summed from the items again
+    pub(crate) fn merge(mut self, pages: Vec<Positions>) -> Positions {
+        if pages.is_empty() {
+            return self;
+        }
+        for page in pages {
+            self.items.extend(page.items);
+        }
+
+        let sum = |amount: fn(&PositionItem) -> Money| self.items.iter().map(amount).sum::<Money>();
+        let market_value = sum(|item| item.market_value);
+        let cost = sum(|item| item.cost);
+        let gainloss = sum(|item| item.gainloss);
+        let daychange = sum(|item| item.change * Money::from(item.quantity * item.multiplier()));
+        let percent = |amount: Money, base: Money| {
+            let base = to_f64(base);
+            if base == 0.0 {
+                return 0.0;
+            }
+            (to_f64(amount) / base * 100.0 * 10_000.0).round() / 10_000.0
+        };
+
+        self.total_market_value = market_value;
+        self.total_gainloss = gainloss;
+        self.total_gainloss_percent = percent(gainloss, cost);
+        self.total_daychange_amount = daychange;
+        self.total_daychange_percent = percent(daychange, market_value - daychange);
+        self.page = 1;
+        self.pages = 1;
+        self.per_page = self.items.len() as u32;
+        self.total = self.items.len() as u32;
+        self
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PositionItem {
     pub quantity: i32,
-    pub last: f64,
-    pub bid: f64,
-    pub ask: f64,
+    pub last: Money,
+    pub bid: Money,
+    pub ask: Money,
     pub vol: u32,
-    pub close: f64,
-    pub cost: f64,
-    pub unit_cost: f64,
+    pub close: Money,
+    pub cost: Money,
+    pub unit_cost: Money,
     pub today_share: u32,
-    pub today_exe_price: f64,
-    pub sec_type: u8,
-    pub market_value: f64,
-    pub change: f64,
+    pub today_exe_price: Money,
+    pub sec_type: SecurityType,
+    pub market_value: Money,
+    pub change: Money,
     pub time: String,
     pub company_name: String,
     pub avg_vol: u32,
-    pub eps: f64,
+    pub eps: Money,
     pub pe: f64,
-    pub div_share: f64,
+    pub div_share: Money,
     pub r#yield: f64,
     pub ex_div_date: String,
     pub div_date: String,
@@ -118,33 +160,125 @@ pub struct PositionItem {
     #[serde(rename = "5yr_growth")]
     pub five_yr_growth: f64,
     pub beta: f64,
-    pub annual_div_rate: f64,
+    pub annual_div_rate: Money,
     #[serde(rename = "52w_high")]
-    pub fifty_two_w_high: f64,
+    pub fifty_two_w_high: Money,
     #[serde(rename = "52w_low")]
-    pub fifty_two_w_low: f64,
+    pub fifty_two_w_low: Money,
     pub has_lots: bool,
     pub asksize: u32,
     pub bidsize: u32,
-    pub open_px: f64,
-    pub day_high: f64,
-    pub day_low: f64,
+    pub open_px: Money,
+    pub day_high: Money,
+    pub day_low: Money,
     pub purchase_date: String,
     pub day_held: u32,
-    pub adj_cost: f64,
-    pub adj_unit_cost: f64,
-    pub adj_gainloss: f64,
+    pub adj_cost: Money,
+    pub adj_unit_cost: Money,
+    pub adj_gainloss: Money,
     pub adj_gainloss_percent: f64,
     pub change_percent: f64,
     pub drip: bool,
     pub loan: bool,
-    pub gainloss: f64,
+    pub gainloss: Money,
     pub gainloss_percent: f64,
+    /// Symbol as sent by the server, the padded OCC symbol for options.
     pub symbol: String,
-    // NOTE: for option parse result
-    pub expiration_date: Option<DateTime<Utc>>,
-    pub strike_price: Option<f64>,
-    pub is_call: Option<bool>,
+    /// Structured form of `symbol`, resolved when [`Positions`] are deserialized.
+    #[serde(default)]
+    pub instrument: Instrument,
+}
+
+// NOTE: the server doesn't send `instrument`, it's resolved from `sec_type` and `symbol` unless
+// the items were serialized with it
+fn deserialize_position_items<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Vec<PositionItem>, D::Error> {
+    let mut items = Vec::<PositionItem>::deserialize(deserializer)?;
+    for item in &mut items {
+        if item.instrument == Instrument::Other {
+            item.instrument = item.resolve_instrument();
+        }
+    }
+    Ok(items)
+}
+
+impl PositionItem {
+    fn resolve_instrument(&self) -> Instrument {
+        match self.sec_type {
+            SecurityType::Equity | SecurityType::Etf => Instrument::Equity,
+            SecurityType::Option => match self.symbol.parse::<OccSymbol>() {
+                Ok(symbol) => {
+                    let adjusted = symbol.is_adjusted();
+                    if adjusted {
+                        log::warn!(
+                            "adjusted option position {}, its multiplier of 100 is assumed",
+                            self.symbol
+                        );
+                    }
+                    Instrument::Option {
+                        symbol,
+                        multiplier: self.multiplier(),
+                        adjusted,
+                    }
+                }
+                Err(err) => {
+                    log::warn!("unrecognized option position {}: {err}", self.symbol);
+                    Instrument::Other
+                }
+            },
+            _ => Instrument::Other,
+        }
+    }
+
+    /// Shares per unit of `quantity`, 100 for options.
+    ///
+    /// Positions don't carry the deliverable, so the 100 of an adjusted contract is only assumed,
+    /// see [`OccSymbol::is_adjusted`].
+    pub fn multiplier(&self) -> i32 {
+        if self.sec_type.is_option() { 100 } else { 1 }
+    }
+
+    /// Root, expiry, right and strike of an option position.
+    pub fn option_symbol(&self) -> Result<&OccSymbol> {
+        self.instrument.option_symbol().ok_or_else(|| {
+            Error::new(ErrorKind::ConditionNotMatch, "not an option position")
+                .with_context("symbol", &self.symbol)
+                .with_context("sec_type", self.sec_type.to_string())
+        })
+    }
+}
+
+/// What a [`PositionItem`] holds.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Instrument {
+    /// Stocks and ETFs
+    Equity,
+    Option {
+        symbol: OccSymbol,
+        /// Shares per contract
+        multiplier: i32,
+        /// Non-standard deliverable, `multiplier` is then only assumed, see
+        /// [`OccSymbol::is_adjusted`].
+        adjusted: bool,
+    },
+    /// Mutual funds, fixed income, unknown security types and options whose symbol could not be parsed
+    #[default]
+    Other,
+}
+
+impl Instrument {
+    pub fn is_option(&self) -> bool {
+        matches!(self, Self::Option { .. })
+    }
+
+    pub fn option_symbol(&self) -> Option<&OccSymbol> {
+        match self {
+            Self::Option { symbol, .. } => Some(symbol),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -162,25 +296,25 @@ pub struct BalanceResult {
     pub account: String,
     pub freetrade_count: i64,
     pub last_freetrade_date: String,
-    pub total_account_value: f64,
-    pub total_account_change: f64,
-    pub long_stock_change: f64,
-    pub short_stock_change: f64,
-    pub long_option_change: f64,
-    pub short_option_change: f64,
-    pub fixed_income_change: f64,
-    pub mutual_funds_change: f64,
-    pub cash_balance: f64,
-    pub cash_balance_change: f64,
-    pub margin_balance: f64,
-    pub margin_balance_change: f64,
-    pub margin_buying_power: f64,
-    pub long_stock_value: f64,
-    pub long_option_value: f64,
-    pub short_option_value: f64,
-    pub non_margin_buying_power: f64,
-    pub daytrade_buying_power: f64,
-    pub money_locked_by_pending_orders: f64,
+    pub total_account_value: Money,
+    pub total_account_change: Money,
+    pub long_stock_change: Money,
+    pub short_stock_change: Money,
+    pub long_option_change: Money,
+    pub short_option_change: Money,
+    pub fixed_income_change: Money,
+    pub mutual_funds_change: Money,
+    pub cash_balance: Money,
+    pub cash_balance_change: Money,
+    pub margin_balance: Money,
+    pub margin_balance_change: Money,
+    pub margin_buying_power: Money,
+    pub long_stock_value: Money,
+    pub long_option_value: Money,
+    pub short_option_value: Money,
+    pub non_margin_buying_power: Money,
+    pub daytrade_buying_power: Money,
+    pub money_locked_by_pending_orders: Money,
 }
 
 // =================== Account History ====================
@@ -197,13 +331,37 @@ pub struct AccountHistory {
     pub total: u32,
 }
 
+/// Narrows [`FtAccount::history_stream`](crate::account::FtAccount::history_stream) to a date span
+/// within the server range, both ends inclusive.
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct HistoryFilter {
+    #[builder(default, setter(strip_option))]
+    pub start: Option<NaiveDate>,
+    #[builder(default, setter(strip_option))]
+    pub end: Option<NaiveDate>,
+    /// Items fetched per request, the server default of 200 when unset.
+    #[builder(default, setter(strip_option))]
+    pub per_page: Option<u32>,
+}
+
+impl HistoryFilter {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start.is_none_or(|start| start <= date) && self.end.is_none_or(|end| date <= end)
+    }
+
+    pub(crate) fn is_dated(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct HistoryItem {
+    /// Report date as sent by the server, see [`HistoryItem::parsed_report_date`].
     pub report_date: String,
     pub trans_str: String,
     pub quantity: i64,
-    pub trade_price: f64,
-    pub amount: f64,
+    pub trade_price: Money,
+    pub amount: Money,
     pub description: String,
     #[serde(rename = "descriptionArray")]
     pub description_array: Vec<String>,
@@ -213,9 +371,136 @@ pub struct HistoryItem {
     pub short_desc: Option<String>,
 }
 
+impl HistoryItem {
+    /// Parsed report date, `None` when the server sends a format we don't know.
+    pub fn parsed_report_date(&self) -> Option<NaiveDate> {
+        let raw = self.report_date.trim();
+        REPORT_DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+    }
+
+    /// Classifies the item from `trans_str`, falling back to the descriptions.
+    pub fn kind(&self) -> TransactionKind {
+        std::iter::once(&self.trans_str)
+            .chain(std::iter::once(&self.description))
+            .chain(&self.description_array)
+            .map(|text| TransactionKind::classify(text))
+            .find(|kind| *kind != TransactionKind::Other)
+            .unwrap_or(TransactionKind::Other)
+    }
+}
+
+const REPORT_DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%m/%d/%Y"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionKind {
+    Buy,
+    Sell,
+    /// Sell short
+    Short,
+    /// Buy to cover
+    Cover,
+    Dividend,
+    Interest,
+    Fee,
+    Deposit,
+    Withdrawal,
+    OptionAssignment,
+    OptionExercise,
+    OptionExpiration,
+    Journal,
+    /// Splits, mergers, spin-offs, symbol changes and other reorganizations
+    CorporateAction,
+    /// Not recognized, the raw text stays on the [`HistoryItem`]
+    Other,
+}
+
+impl TransactionKind {
+    // NOTE: whole words or phrases, tried in order, e.g. "Sold Short" is a short, "Option Assigned -
+    // Sold" an assignment and "SHORT TERM CAP GAIN" a fund distribution rather than a short sale
+    const KEYWORDS: [(TransactionKind, &'static [&'static str]); 14] = [
+        (Self::OptionAssignment, &["assign", "assigned", "assignment"]),
+        (Self::OptionExercise, &["exercise", "exercised"]),
+        (Self::OptionExpiration, &["expire", "expired", "expiration"]),
+        (
+            Self::Dividend,
+            &[
+                "dividend",
+                "dividends",
+                "div",
+                "cap gain",
+                "capital gain",
+                "capital gains",
+                "distribution",
+            ],
+        ),
+        (Self::Cover, &["cover"]),
+        (Self::Short, &["short", "sold short", "sell short", "short sale"]),
+        (Self::Interest, &["interest"]),
+        (Self::Fee, &["fee", "fees", "commission"]),
+        (Self::Journal, &["journal", "jrnl", "transfer between"]),
+        (
+            Self::CorporateAction,
+            &[
+                "split",
+                "reverse split",
+                "merger",
+                "spinoff",
+                "spin off",
+                "reorg",
+                "reorganization",
+                "symbol change",
+                "name change",
+                "exchange offer",
+                "tender offer",
+            ],
+        ),
+        (Self::Deposit, &["deposit", "ach in", "wire in", "contribution"]),
+        (
+            Self::Withdrawal,
+            &["withdraw", "withdrawal", "ach out", "wire out", "disbursement"],
+        ),
+        (Self::Buy, &["bought", "buy"]),
+        (Self::Sell, &["sold", "sell"]),
+    ];
+
+    /// Case-insensitive match of whole words against a history text, e.g. `"Bought"` or
+    /// `"CASH DIV AAPL"`.
+    pub fn classify(text: &str) -> Self {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect();
+        let contains = |phrase: &str| {
+            let phrase: Vec<&str> = phrase.split(' ').collect();
+            words
+                .windows(phrase.len())
+                .any(|window| window == phrase.as_slice())
+        };
+        Self::KEYWORDS
+            .iter()
+            .find(|(_, phrases)| phrases.iter().any(|phrase| contains(phrase)))
+            .map_or(Self::Other, |(kind, _)| *kind)
+    }
+
+    pub fn is_trade(&self) -> bool {
+        matches!(self, Self::Buy | Self::Sell | Self::Short | Self::Cover)
+    }
+
+    pub fn is_option_event(&self) -> bool {
+        matches!(
+            self,
+            Self::OptionAssignment | Self::OptionExercise | Self::OptionExpiration
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::option::{OptionRight, Strike};
     use serde_json::json;
 
     #[test]
@@ -392,8 +677,43 @@ mod tests {
         assert_eq!(position.items.len(), 2);
         assert_eq!(position.items[0].symbol, "ABCD260116C00003000");
         assert_eq!(position.items[1].symbol, "AAPL");
-        assert_eq!(position.total_market_value, 424250.36);
+        let option = position.items[0].option_symbol().unwrap();
+        assert_eq!(option.root, "ABCD");
+        assert_eq!(option.expiry, NaiveDate::from_ymd_opt(2026, 1, 16).unwrap());
+        assert!(option.is_call());
+        assert_eq!(option.strike.to_f64(), 3.0);
+        let err = position.items[1].option_symbol().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConditionNotMatch);
+        assert_eq!(position.total_market_value.to_string(), "424250.36");
         assert_eq!(position.total_gainloss_percent, 43.8973);
+
+        assert_eq!(
+            position.items[0].instrument,
+            Instrument::Option {
+                symbol: OccSymbol::new(
+                    "ABCD",
+                    NaiveDate::from_ymd_opt(2026, 1, 16).unwrap(),
+                    OptionRight::Call,
+                    Strike::from_thousandths(3_000).unwrap(),
+                ),
+                multiplier: 100,
+                adjusted: false,
+            }
+        );
+        assert_eq!(position.items[1].instrument, Instrument::Equity);
+
+        let mut item = position.items[1].clone();
+        item.sec_type = SecurityType::Etf;
+        assert_eq!(item.resolve_instrument(), Instrument::Equity);
+        item.sec_type = SecurityType::MutualFund;
+        assert_eq!(item.resolve_instrument(), Instrument::Other);
+
+        // The instrument survives a round trip through the serialized positions
+        let value = serde_json::to_value(&position).unwrap();
+        assert_eq!(value["items"][0]["instrument"]["type"], "option");
+        assert_eq!(value["items"][0]["instrument"]["symbol"], "ABCD260116C00003000");
+        let again: Positions = serde_json::from_value(value).unwrap();
+        assert_eq!(again.items[0].instrument, position.items[0].instrument);
     }
 
     #[test]
@@ -432,9 +752,65 @@ mod tests {
         let balance: Balance = serde_json::from_value(json_data).unwrap();
         assert_eq!(balance.status_code, 200);
         let result = balance.result.unwrap();
-        assert_eq!(result.cash_balance, 12345.67);
-        assert_eq!(result.long_stock_change, 8652.99);
-        assert_eq!(result.daytrade_buying_power, 500000.11);
-        assert_eq!(result.margin_balance, -123.11);
+        assert_eq!(result.cash_balance.to_string(), "12345.67");
+        assert_eq!(result.long_stock_change.to_string(), "8652.99");
+        assert_eq!(result.daytrade_buying_power.to_string(), "500000.11");
+        assert_eq!(result.margin_balance.to_string(), "-123.11");
+    }
+
+    #[test]
+    fn test_history_item() {
+        let json_data = json!({
+            "report_date": "08/15/2025",
+            "trans_str": "Other",
+            "quantity": 0,
+            "trade_price": 0.0,
+            "amount": 12.34,
+            "description": "CASH DIV ON 50 SHS MSFT",
+            "descriptionArray": ["CASH DIV ON 50 SHS MSFT"],
+            "symbol": "MSFT",
+            "account_type": "1",
+            "shortDesc": null
+        });
+
+        let item: HistoryItem = serde_json::from_value(json_data).unwrap();
+        assert_eq!(item.parsed_report_date(), NaiveDate::from_ymd_opt(2025, 8, 15));
+        assert_eq!(item.kind(), TransactionKind::Dividend);
+
+        let value = serde_json::to_value(&item).unwrap();
+        assert_eq!(value["report_date"], "08/15/2025");
+    }
+
+    #[test]
+    fn test_transaction_kind() {
+        let cases = [
+            ("Bought", TransactionKind::Buy),
+            ("SOLD", TransactionKind::Sell),
+            ("Sold Short", TransactionKind::Short),
+            ("Buy to Cover", TransactionKind::Cover),
+            ("Dividend", TransactionKind::Dividend),
+            ("Interest", TransactionKind::Interest),
+            ("ADR Fee", TransactionKind::Fee),
+            ("ACH Deposit", TransactionKind::Deposit),
+            ("Wire Out", TransactionKind::Withdrawal),
+            ("Option Assigned - Sold", TransactionKind::OptionAssignment),
+            ("Option Exercised", TransactionKind::OptionExercise),
+            ("Option Expired", TransactionKind::OptionExpiration),
+            ("Journal", TransactionKind::Journal),
+            ("Stock Split", TransactionKind::CorporateAction),
+            ("SHORT TERM CAP GAIN", TransactionKind::Dividend),
+            ("LONG TERM CAPITAL GAIN DISTRIBUTION", TransactionKind::Dividend),
+            ("Coffee Co bought", TransactionKind::Buy),
+            ("SPINNAKER INC", TransactionKind::Other),
+            ("NASDAQ EXCHANGE LISTING", TransactionKind::Other),
+            ("COFFEE HOLDINGS CASH DIV", TransactionKind::Dividend),
+            ("Other", TransactionKind::Other),
+            ("", TransactionKind::Other),
+        ];
+        for (text, kind) in cases {
+            assert_eq!(TransactionKind::classify(text), kind, "{text}");
+        }
+        assert!(TransactionKind::Short.is_trade());
+        assert!(TransactionKind::OptionExpiration.is_option_event());
     }
 }
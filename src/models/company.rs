@@ -54,7 +54,7 @@ pub struct Fundamental {
     pub net_margin: Option<f64>,
     pub diluted_eps: Option<f64>,
     pub forward_pe: Option<f64>,
-    pub dividend_ytd: Option<f64>
+    pub dividend_ytd: Option<f64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1,7 +1,10 @@
 pub mod account;
 pub mod company;
+pub mod money;
+pub mod option;
 pub mod order;
 pub mod quote;
+pub mod security;
 pub mod session;
 pub mod utils;
 pub mod watchlist;
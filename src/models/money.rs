@@ -0,0 +1,46 @@
+//! Prices, costs and balances are `f64` unless the `decimal` feature is enabled, which makes them
+//! an exact [`rust_decimal::Decimal`].
+//!
+//! With `decimal`, amounts are read from JSON numbers or strings and written back as strings,
+//! so a serialized model parses to the same values.
+
+#[cfg(not(feature = "decimal"))]
+pub type Money = f64;
+
+#[cfg(feature = "decimal")]
+pub type Money = rust_decimal::Decimal;
+
+#[cfg(not(feature = "decimal"))]
+pub(crate) fn to_f64(amount: Money) -> f64 {
+    amount
+}
+
+#[cfg(feature = "decimal")]
+pub(crate) fn to_f64(amount: Money) -> f64 {
+    use rust_decimal::prelude::ToPrimitive;
+    amount.to_f64().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_money_round_trip() {
+        let amount: Money = serde_json::from_value(json!(2174.87)).unwrap();
+        assert_eq!(amount.to_string(), "2174.87");
+
+        let again: Money = serde_json::from_value(serde_json::to_value(amount).unwrap()).unwrap();
+        assert_eq!(again, amount);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_money_is_exact() {
+        let a: Money = serde_json::from_value(json!(0.1)).unwrap();
+        let b: Money = serde_json::from_value(json!("0.2")).unwrap();
+        assert_eq!((a + b).to_string(), "0.3");
+        assert_eq!(serde_json::to_value(a + b).unwrap(), json!("0.3"));
+    }
+}
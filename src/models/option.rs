@@ -0,0 +1,461 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::money::Money;
+use crate::models::utils::yyyymmdd;
+use chrono::{Datelike, NaiveDate};
+use http::StatusCode;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+// ==================== OCC Symbol ====================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OptionRight {
+    #[serde(rename = "C")]
+    Call,
+    #[serde(rename = "P")]
+    Put,
+}
+
+impl OptionRight {
+    pub fn as_char(&self) -> char {
+        match self {
+            OptionRight::Call => 'C',
+            OptionRight::Put => 'P',
+        }
+    }
+}
+
+/// Exact strike price, kept in thousandths of a dollar as in the OCC symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Strike(u32);
+
+impl Strike {
+    /// Largest strike the 8 digits of an OCC symbol can hold, 99999.999.
+    pub const MAX: Strike = Strike(99_999_999);
+
+    pub fn from_thousandths(thousandths: u32) -> Option<Self> {
+        (thousandths <= Self::MAX.0).then_some(Strike(thousandths))
+    }
+
+    pub fn thousandths(&self) -> u32 {
+        self.0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    fn from_f64(price: f64) -> Option<Self> {
+        let thousandths = (price * 1000.0).round();
+        (price.is_finite() && thousandths >= 0.0 && thousandths <= f64::from(Self::MAX.0))
+            .then_some(Strike(thousandths as u32))
+    }
+}
+
+impl fmt::Display for Strike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (units, fraction) = (self.0 / 1000, self.0 % 1000);
+        if fraction == 0 {
+            return write!(f, "{units}");
+        }
+        let fraction = format!("{fraction:03}");
+        write!(f, "{units}.{}", fraction.trim_end_matches('0'))
+    }
+}
+
+impl FromStr for Strike {
+    type Err = Error;
+
+    /// Parse a decimal strike with at most 3 decimals, e.g. `"2.5"`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::new(ErrorKind::ConfigInvalid, "invalid strike").with_context("strike", s);
+        let (units, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if units.is_empty() || !all_digits(units) || !all_digits(fraction) || fraction.len() > 3 {
+            return Err(invalid());
+        }
+        let units: u32 = units.parse().map_err(|_| invalid())?;
+        let fraction: u32 = format!("{fraction:0<3}").parse().map_err(|_| invalid())?;
+        units
+            .checked_mul(1000)
+            .and_then(|units| units.checked_add(fraction))
+            .and_then(Strike::from_thousandths)
+            .ok_or_else(invalid)
+    }
+}
+
+// NOTE: sent as a JSON number, e.g. `"strike_price": 602.5`
+impl Serialize for Strike {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for Strike {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct StrikeVisitor;
+
+        impl serde::de::Visitor<'_> for StrikeVisitor {
+            type Value = Strike;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a strike price")
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, v: f64) -> std::result::Result<Strike, E> {
+                Strike::from_f64(v).ok_or_else(|| E::custom(format!("invalid strike {v}")))
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> std::result::Result<Strike, E> {
+                self.visit_f64(v as f64)
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> std::result::Result<Strike, E> {
+                self.visit_f64(v as f64)
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> std::result::Result<Strike, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(StrikeVisitor)
+    }
+}
+
+/// OCC option symbol, e.g. `SPY250919C00600000`: root, `YYMMDD` expiry, right and 8 digit strike.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OccSymbol {
+    pub root: String,
+    pub expiry: NaiveDate,
+    pub right: OptionRight,
+    pub strike: Strike,
+}
+
+impl OccSymbol {
+    pub fn new(root: impl Into<String>, expiry: NaiveDate, right: OptionRight, strike: Strike) -> Self {
+        Self {
+            root: root.into().trim().to_uppercase(),
+            expiry,
+            right,
+            strike,
+        }
+    }
+
+    pub fn is_call(&self) -> bool {
+        self.right == OptionRight::Call
+    }
+
+    pub fn is_put(&self) -> bool {
+        self.right == OptionRight::Put
+    }
+
+    /// Adjusted contracts get a root with a numeric suffix after a split or merger, e.g. `AAPL1`,
+    /// and may deliver something other than 100 shares.
+    pub fn is_adjusted(&self) -> bool {
+        self.root.ends_with(|c: char| c.is_ascii_digit())
+    }
+}
+
+impl fmt::Display for OccSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{:02}{:02}{:02}{}{:08}",
+            self.root,
+            self.expiry.year() % 100,
+            self.expiry.month(),
+            self.expiry.day(),
+            self.right.as_char(),
+            self.strike.thousandths()
+        )
+    }
+}
+
+// NOTE: also accepts the space padded 21 character form, e.g. `SPY   250919C00600000`
+impl FromStr for OccSymbol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid =
+            || Error::new(ErrorKind::ConfigInvalid, "invalid option symbol").with_context("symbol", s);
+        let symbol = s.trim();
+        if !symbol.is_ascii() {
+            return Err(invalid());
+        }
+        let split = symbol.len().checked_sub(15).ok_or_else(invalid)?;
+        let (root, contract) = symbol.split_at(split);
+        let root = root.trim_end();
+        let (expiry, contract) = contract.split_at(6);
+        let (right, strike) = contract.split_at(1);
+
+        if root.is_empty() || !root.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+            return Err(invalid());
+        }
+        if !expiry.chars().chain(strike.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let right = match right {
+            "C" | "c" => OptionRight::Call,
+            "P" | "p" => OptionRight::Put,
+            _ => return Err(invalid()),
+        };
+        // Two digit years are always 20xx, OCC symbols started in 2010
+        let expiry = NaiveDate::parse_from_str(&format!("20{expiry}"), "%Y%m%d")
+            .map_err(|e| invalid().set_source(e))?;
+        let strike = strike
+            .parse()
+            .ok()
+            .and_then(Strike::from_thousandths)
+            .ok_or_else(invalid)?;
+        Ok(Self::new(root, expiry, right, strike))
+    }
+}
+
+impl Serialize for OccSymbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for OccSymbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// ==================== Expirations ====================
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionExpirationsResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub items: Option<Vec<OptionExpiration>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionExpiration {
+    #[serde(with = "yyyymmdd")]
+    pub exp_date: NaiveDate,
+    pub day_left: i64,
+    /// `W` for weeklies, `M` for monthlies, `Q` for quarterlies.
+    pub exp_type: String,
+}
+
+// ==================== Chain ====================
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionChainResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub items: Option<Vec<ChainStrike>>,
+}
+
+/// Calls and puts of one expiration, by ascending strike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionChain {
+    pub symbol: String,
+    pub expiration: NaiveDate,
+    pub strikes: Vec<ChainStrike>,
+}
+
+impl OptionChain {
+    pub fn calls(&self) -> impl Iterator<Item = &ChainContract> {
+        self.strikes.iter().filter_map(|s| s.call.as_ref())
+    }
+
+    pub fn puts(&self) -> impl Iterator<Item = &ChainContract> {
+        self.strikes.iter().filter_map(|s| s.put.as_ref())
+    }
+
+    pub fn strike(&self, strike: Strike) -> Option<&ChainStrike> {
+        self.strikes.iter().find(|s| s.strike_price == strike)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainStrike {
+    pub strike_price: Strike,
+    pub call: Option<ChainContract>,
+    pub put: Option<ChainContract>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainContract {
+    pub symbol: OccSymbol,
+    pub bid: Money,
+    pub bid_size: u32,
+    pub ask: Money,
+    pub ask_size: u32,
+    pub last: Money,
+    pub change: Money,
+    #[serde(rename = "vol")]
+    pub volume: u64,
+    pub open_interest: u64,
+    /// Only filled when the greeks request succeeded.
+    #[serde(default)]
+    pub greeks: Option<Greeks>,
+}
+
+// ==================== Greeks ====================
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GreeksResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub result: Option<Vec<ContractGreeks>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractGreeks {
+    pub symbol: OccSymbol,
+    #[serde(flatten)]
+    pub greeks: Greeks,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    pub theta: f64,
+    pub vega: f64,
+    pub rho: f64,
+    #[serde(rename = "iv")]
+    pub implied_volatility: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_option_chain_deserialization() {
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "items": [
+                { "exp_date": "20250919", "day_left": 3, "exp_type": "M" }
+            ]
+        });
+        let resp: OptionExpirationsResponse = serde_json::from_value(json_data).unwrap();
+        let items = resp.items.unwrap();
+        assert_eq!(items[0].exp_date, NaiveDate::from_ymd_opt(2025, 9, 19).unwrap());
+
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "items": [
+                {
+                    "strike_price": 600,
+                    "call": {
+                        "symbol": "SPY250919C00600000",
+                        "bid": 6.15,
+                        "bid_size": 12,
+                        "ask": 6.25,
+                        "ask_size": 30,
+                        "last": 6.2,
+                        "change": 0.35,
+                        "vol": 1520,
+                        "open_interest": 20311
+                    },
+                    "put": null
+                }
+            ]
+        });
+        let resp: OptionChainResponse = serde_json::from_value(json_data).unwrap();
+        let strike = &resp.items.unwrap()[0];
+        let call = strike.call.as_ref().unwrap();
+        assert_eq!(call.volume, 1520);
+        assert_eq!(call.symbol.strike.to_string(), "600");
+        assert_eq!(strike.strike_price, call.symbol.strike);
+        assert!(call.greeks.is_none());
+        assert!(strike.put.is_none());
+
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "result": [
+                {
+                    "symbol": "SPY250919C00600000",
+                    "delta": 0.52,
+                    "gamma": 0.031,
+                    "theta": -0.41,
+                    "vega": 0.22,
+                    "rho": 0.05,
+                    "iv": 0.183
+                }
+            ]
+        });
+        let resp: GreeksResponse = serde_json::from_value(json_data).unwrap();
+        assert_eq!(resp.result.unwrap()[0].greeks.implied_volatility, 0.183);
+    }
+
+    #[test]
+    fn test_occ_symbol() {
+        let symbol: OccSymbol = "UNH250822P00250000".parse().unwrap();
+        assert_eq!(symbol.root, "UNH");
+        assert_eq!(symbol.expiry, NaiveDate::from_ymd_opt(2025, 8, 22).unwrap());
+        assert_eq!(symbol.right, OptionRight::Put);
+        assert_eq!(symbol.strike.thousandths(), 250_000);
+        assert_eq!(symbol.to_string(), "UNH250822P00250000");
+
+        let symbol: OccSymbol = "brk.b260116c00002500".parse().unwrap();
+        assert_eq!(symbol.root, "BRK.B");
+        assert_eq!(symbol.strike.to_string(), "2.5");
+        assert_eq!(symbol.to_string(), "BRK.B260116C00002500");
+
+        let padded: OccSymbol = "SPY   250919C00600000".parse().unwrap();
+        assert_eq!(padded.to_string(), "SPY250919C00600000");
+        assert!(!padded.is_adjusted());
+
+        let adjusted: OccSymbol = "AAPL1260116C00150000".parse().unwrap();
+        assert_eq!(adjusted.root, "AAPL1");
+        assert!(adjusted.is_adjusted());
+
+        let json = serde_json::to_value(&padded).unwrap();
+        assert_eq!(json, json!("SPY250919C00600000"));
+        assert_eq!(serde_json::from_value::<OccSymbol>(json).unwrap(), padded);
+
+        for symbol in [
+            "",
+            "P",
+            "250822P00250000",
+            "UNH250822X00250000",
+            "UNH251322P00250000",
+            "UNH250822P0025000A",
+            "UNH/250822P00250000",
+            "ÜNH250822P00250000",
+        ] {
+            assert!(symbol.parse::<OccSymbol>().is_err(), "{symbol}");
+        }
+        assert!(serde_json::from_value::<OccSymbol>(json!("AAPL")).is_err());
+    }
+
+    #[test]
+    fn test_strike() {
+        assert_eq!("600".parse::<Strike>().unwrap().thousandths(), 600_000);
+        assert_eq!("2.5".parse::<Strike>().unwrap().to_string(), "2.5");
+        assert_eq!("0.125".parse::<Strike>().unwrap().thousandths(), 125);
+        for strike in ["", ".5", "1.2345", "-1", "100000", "1e3"] {
+            assert!(strike.parse::<Strike>().is_err(), "{strike}");
+        }
+
+        let strike: Strike = serde_json::from_value(json!(602.5)).unwrap();
+        assert_eq!(strike.thousandths(), 602_500);
+        assert_eq!(serde_json::to_value(strike).unwrap(), json!(602.5));
+        let strike: Strike = serde_json::from_value(json!("2.5")).unwrap();
+        assert_eq!(strike.thousandths(), 2_500);
+        assert!(serde_json::from_value::<Strike>(json!(-1)).is_err());
+    }
+}
@@ -1 +1,1369 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::money::{Money, to_f64};
+use crate::models::option::OccSymbol;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use typed_builder::TypedBuilder;
 
+// ==================== Order Parameters ====================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    #[serde(rename = "B")]
+    Buy,
+    #[serde(rename = "S")]
+    Sell,
+    #[serde(rename = "SS")]
+    SellShort,
+    #[serde(rename = "BC")]
+    BuyToCover,
+    /// Options only, closing positions use `BuyToCover` and `Sell`.
+    #[serde(rename = "BO")]
+    BuyToOpen,
+    /// Options only.
+    #[serde(rename = "SO")]
+    SellToOpen,
+}
+
+impl OrderSide {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "B",
+            OrderSide::Sell => "S",
+            OrderSide::SellShort => "SS",
+            OrderSide::BuyToCover => "BC",
+            OrderSide::BuyToOpen => "BO",
+            OrderSide::SellToOpen => "SO",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionAction {
+    BuyToOpen,
+    SellToOpen,
+    BuyToClose,
+    SellToClose,
+}
+
+impl OptionAction {
+    pub fn side(&self) -> OrderSide {
+        match self {
+            OptionAction::BuyToOpen => OrderSide::BuyToOpen,
+            OptionAction::SellToOpen => OrderSide::SellToOpen,
+            OptionAction::BuyToClose => OrderSide::BuyToCover,
+            OptionAction::SellToClose => OrderSide::Sell,
+        }
+    }
+
+    /// Lowest account `option_level` allowed to send it, `None` when it can't be told locally.
+    ///
+    /// A sale to open needs level 1 when it's covered by shares and a higher level when it's
+    /// uncovered, only the server knows which, so [`OptionAction::SellToOpen`] isn't checked
+    /// before sending.
+    pub fn required_option_level(&self) -> Option<i64> {
+        match self {
+            OptionAction::BuyToOpen => Some(2),
+            OptionAction::BuyToClose | OptionAction::SellToClose => Some(1),
+            OptionAction::SellToOpen => None,
+        }
+    }
+}
+
+/// Price type of an order, carrying the prices it needs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderType {
+    Market,
+    Limit { limit_price: Money },
+    Stop { stop_price: Money },
+    StopLimit { stop_price: Money, limit_price: Money },
+}
+
+impl OrderType {
+    /// Firstrade `price_type` code.
+    pub fn price_type(&self) -> &'static str {
+        match self {
+            OrderType::Market => "1",
+            OrderType::Limit { .. } => "2",
+            OrderType::Stop { .. } => "3",
+            OrderType::StopLimit { .. } => "4",
+        }
+    }
+
+    pub fn limit_price(&self) -> Option<Money> {
+        match self {
+            OrderType::Limit { limit_price } | OrderType::StopLimit { limit_price, .. } => Some(*limit_price),
+            _ => None,
+        }
+    }
+
+    pub fn stop_price(&self) -> Option<Money> {
+        match self {
+            OrderType::Stop { stop_price } | OrderType::StopLimit { stop_price, .. } => Some(*stop_price),
+            _ => None,
+        }
+    }
+
+    /// Rebuild the order type from a `price_type` code and the prices of an existing order.
+    pub fn from_price_type(
+        price_type: &str,
+        limit_price: Option<Money>,
+        stop_price: Option<Money>,
+    ) -> Option<Self> {
+        match (price_type, limit_price, stop_price) {
+            ("1", _, _) => Some(OrderType::Market),
+            ("2", Some(limit_price), _) => Some(OrderType::Limit { limit_price }),
+            ("3", _, Some(stop_price)) => Some(OrderType::Stop { stop_price }),
+            ("4", Some(limit_price), Some(stop_price)) => Some(OrderType::StopLimit {
+                stop_price,
+                limit_price,
+            }),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (field, price) in [
+            ("limit_price", self.limit_price()),
+            ("stop_price", self.stop_price()),
+        ] {
+            if let Some(price) = price
+                && !is_positive(price)
+            {
+                return Err(Error::new(ErrorKind::ConfigInvalid, "price must be positive")
+                    .with_context(field, price.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[inline]
+fn is_positive(price: Money) -> bool {
+    let price = to_f64(price);
+    price.is_finite() && price > 0.0
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Duration {
+    #[default]
+    #[serde(rename = "0")]
+    Day,
+    /// Firstrade cancels GTC orders after 90 days.
+    #[serde(rename = "1")]
+    GoodTillCanceled,
+}
+
+impl Duration {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Duration::Day => "0",
+            Duration::GoodTillCanceled => "1",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderInstruction {
+    #[serde(rename = "1")]
+    AllOrNone,
+    #[serde(rename = "4")]
+    OnOpen,
+    #[serde(rename = "5")]
+    OnClose,
+}
+
+impl OrderInstruction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderInstruction::AllOrNone => "1",
+            OrderInstruction::OnOpen => "4",
+            OrderInstruction::OnClose => "5",
+        }
+    }
+}
+
+// ==================== Stock Order ====================
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct StockOrder {
+    #[builder(setter(into))]
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: u32,
+    pub order_type: OrderType,
+    #[builder(default)]
+    pub duration: Duration,
+    #[builder(default, setter(strip_option))]
+    pub instruction: Option<OrderInstruction>,
+}
+
+impl StockOrder {
+    pub fn validate(&self) -> Result<()> {
+        if self.symbol.trim().is_empty() {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "symbol is empty"));
+        }
+        if self.quantity == 0 {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "quantity must be positive")
+                .with_context("symbol", &self.symbol));
+        }
+        if matches!(self.side, OrderSide::BuyToOpen | OrderSide::SellToOpen) {
+            return Err(
+                Error::new(ErrorKind::ConfigInvalid, "option side on a stock order")
+                    .with_context("side", self.side.as_str()),
+            );
+        }
+        self.order_type.validate()
+    }
+
+    pub(crate) fn to_form(&self, account: &str, preview: bool) -> HashMap<&'static str, String> {
+        let mut form = HashMap::from([
+            ("account", account.to_string()),
+            ("symbol", self.symbol.trim().to_uppercase()),
+            ("transaction", self.side.as_str().to_string()),
+            ("shares", self.quantity.to_string()),
+            ("price_type", self.order_type.price_type().to_string()),
+            ("duration", self.duration.as_str().to_string()),
+            ("preview", preview.to_string()),
+        ]);
+        if let Some(limit_price) = self.order_type.limit_price() {
+            form.insert("limit_price", limit_price.to_string());
+        }
+        if let Some(stop_price) = self.order_type.stop_price() {
+            form.insert("stop_price", stop_price.to_string());
+        }
+        if let Some(instruction) = self.instruction {
+            form.insert("instructions", instruction.as_str().to_string());
+        }
+        form
+    }
+}
+
+// ==================== Option Order ====================
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+pub struct OptionOrder {
+    pub symbol: OccSymbol,
+    pub action: OptionAction,
+    pub contracts: u32,
+    pub order_type: OrderType,
+    #[builder(default)]
+    pub duration: Duration,
+    #[builder(default, setter(strip_option))]
+    pub instruction: Option<OrderInstruction>,
+}
+
+impl OptionOrder {
+    pub fn validate(&self) -> Result<()> {
+        if self.contracts == 0 {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "contracts must be positive")
+                .with_context("symbol", self.symbol.to_string()));
+        }
+        self.order_type.validate()
+    }
+
+    pub(crate) fn to_form(&self, account: &str, preview: bool) -> HashMap<&'static str, String> {
+        let mut form = HashMap::from([
+            ("account", account.to_string()),
+            ("symbol", self.symbol.to_string()),
+            ("transaction", self.action.side().as_str().to_string()),
+            ("contracts", self.contracts.to_string()),
+            ("price_type", self.order_type.price_type().to_string()),
+            ("duration", self.duration.as_str().to_string()),
+            ("preview", preview.to_string()),
+        ]);
+        if let Some(limit_price) = self.order_type.limit_price() {
+            form.insert("limit_price", limit_price.to_string());
+        }
+        if let Some(stop_price) = self.order_type.stop_price() {
+            form.insert("stop_price", stop_price.to_string());
+        }
+        if let Some(instruction) = self.instruction {
+            form.insert("instructions", instruction.as_str().to_string());
+        }
+        form
+    }
+}
+
+// ==================== Multi-Leg Order ====================
+pub const MAX_LEGS: usize = 4;
+
+// NOTE: leg fields are numbered from 1, e.g. `symbol1`, `transaction1`, `ratio1`
+const LEG_FIELDS: [[&str; 3]; MAX_LEGS] = [
+    ["symbol1", "transaction1", "ratio1"],
+    ["symbol2", "transaction2", "ratio2"],
+    ["symbol3", "transaction3", "ratio3"],
+    ["symbol4", "transaction4", "ratio4"],
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OptionLeg {
+    pub symbol: OccSymbol,
+    pub action: OptionAction,
+    /// Contracts of this leg per spread, e.g. the body of a butterfly is 2.
+    pub ratio: u32,
+}
+
+impl OptionLeg {
+    pub fn new(symbol: OccSymbol, action: OptionAction, ratio: u32) -> Self {
+        Self {
+            symbol,
+            action,
+            ratio,
+        }
+    }
+}
+
+/// Net price of a whole spread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NetPrice {
+    Market,
+    Debit(Money),
+    Credit(Money),
+    Even,
+}
+
+impl NetPrice {
+    fn flip(self) -> Self {
+        match self {
+            NetPrice::Debit(price) => NetPrice::Credit(price),
+            NetPrice::Credit(price) => NetPrice::Debit(price),
+            price => price,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpreadStrategy {
+    /// Only the common checks, any combination of legs.
+    #[default]
+    Custom,
+    Vertical,
+    Calendar,
+    Straddle,
+    Strangle,
+    IronCondor,
+    Butterfly,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiLegOrder {
+    pub legs: Vec<OptionLeg>,
+    /// Number of spreads, each leg trades `quantity * ratio` contracts.
+    pub quantity: u32,
+    pub price: NetPrice,
+    pub duration: Duration,
+    pub strategy: SpreadStrategy,
+}
+
+impl MultiLegOrder {
+    pub fn new(legs: Vec<OptionLeg>, quantity: u32, price: NetPrice) -> Self {
+        Self {
+            legs,
+            quantity,
+            price,
+            duration: Duration::Day,
+            strategy: SpreadStrategy::Custom,
+        }
+    }
+
+    fn with_strategy(mut self, strategy: SpreadStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Buy `long` and sell `short`, same expiration and type.
+    pub fn vertical(long: OccSymbol, short: OccSymbol, quantity: u32, price: NetPrice) -> Self {
+        let legs = vec![
+            OptionLeg::new(long, OptionAction::BuyToOpen, 1),
+            OptionLeg::new(short, OptionAction::SellToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::Vertical)
+    }
+
+    /// Sell the `near` expiration and buy the `far` one, same strike and type.
+    pub fn calendar(near: OccSymbol, far: OccSymbol, quantity: u32, price: NetPrice) -> Self {
+        let legs = vec![
+            OptionLeg::new(near, OptionAction::SellToOpen, 1),
+            OptionLeg::new(far, OptionAction::BuyToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::Calendar)
+    }
+
+    /// Buy a call and a put at the same strike, see [`MultiLegOrder::short`].
+    pub fn straddle(call: OccSymbol, put: OccSymbol, quantity: u32, price: NetPrice) -> Self {
+        let legs = vec![
+            OptionLeg::new(call, OptionAction::BuyToOpen, 1),
+            OptionLeg::new(put, OptionAction::BuyToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::Straddle)
+    }
+
+    /// Buy a call and a put at different strikes, see [`MultiLegOrder::short`].
+    pub fn strangle(call: OccSymbol, put: OccSymbol, quantity: u32, price: NetPrice) -> Self {
+        let legs = vec![
+            OptionLeg::new(call, OptionAction::BuyToOpen, 1),
+            OptionLeg::new(put, OptionAction::BuyToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::Strangle)
+    }
+
+    /// Sell a put spread and a call spread, strikes from lowest to highest.
+    pub fn iron_condor(
+        long_put: OccSymbol,
+        short_put: OccSymbol,
+        short_call: OccSymbol,
+        long_call: OccSymbol,
+        quantity: u32,
+        price: NetPrice,
+    ) -> Self {
+        let legs = vec![
+            OptionLeg::new(long_put, OptionAction::BuyToOpen, 1),
+            OptionLeg::new(short_put, OptionAction::SellToOpen, 1),
+            OptionLeg::new(short_call, OptionAction::SellToOpen, 1),
+            OptionLeg::new(long_call, OptionAction::BuyToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::IronCondor)
+    }
+
+    /// Buy the wings and sell twice the body, strikes from lowest to highest.
+    pub fn butterfly(
+        lower: OccSymbol,
+        middle: OccSymbol,
+        upper: OccSymbol,
+        quantity: u32,
+        price: NetPrice,
+    ) -> Self {
+        let legs = vec![
+            OptionLeg::new(lower, OptionAction::BuyToOpen, 1),
+            OptionLeg::new(middle, OptionAction::SellToOpen, 2),
+            OptionLeg::new(upper, OptionAction::BuyToOpen, 1),
+        ];
+        Self::new(legs, quantity, price).with_strategy(SpreadStrategy::Butterfly)
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Swap buying and selling to open, e.g. a short straddle or a long iron condor.
+    pub fn short(mut self) -> Self {
+        for leg in &mut self.legs {
+            leg.action = match leg.action {
+                OptionAction::BuyToOpen => OptionAction::SellToOpen,
+                OptionAction::SellToOpen => OptionAction::BuyToOpen,
+                action => action,
+            };
+        }
+        self.price = self.price.flip();
+        self
+    }
+
+    /// The order closing the position this order opens.
+    pub fn closing(mut self) -> Self {
+        for leg in &mut self.legs {
+            leg.action = match leg.action {
+                OptionAction::BuyToOpen => OptionAction::SellToClose,
+                OptionAction::SellToOpen => OptionAction::BuyToClose,
+                action => action,
+            };
+        }
+        self.price = self.price.flip();
+        self
+    }
+
+    /// Lowest account `option_level` allowed to send it, spreads with a leg sold to open need 3.
+    pub fn required_option_level(&self) -> i64 {
+        let opening = |action| matches!(action, OptionAction::BuyToOpen | OptionAction::SellToOpen);
+        if self.legs.iter().all(|leg| !opening(leg.action)) {
+            1
+        } else if self.legs.iter().all(|leg| leg.action != OptionAction::SellToOpen) {
+            2
+        } else {
+            3
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(2..=MAX_LEGS).contains(&self.legs.len()) {
+            return Err(
+                Error::new(ErrorKind::ConfigInvalid, "a multi-leg order needs 2 to 4 legs")
+                    .with_context("legs", self.legs.len().to_string()),
+            );
+        }
+        if self.quantity == 0 {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "quantity must be positive"));
+        }
+        if let NetPrice::Debit(price) | NetPrice::Credit(price) = self.price
+            && !is_positive(price)
+        {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "price must be positive")
+                .with_context("price", price.to_string()));
+        }
+
+        if let Some(leg) = self.legs.iter().find(|leg| leg.ratio == 0) {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "ratio must be positive")
+                .with_context("symbol", leg.symbol.to_string()));
+        }
+        let contracts: Vec<&OccSymbol> = self.legs.iter().map(|leg| &leg.symbol).collect();
+
+        let root = contracts[0].root.as_str();
+        if let Some(leg) = contracts.iter().find(|c| c.root != root) {
+            return Err(
+                Error::new(ErrorKind::ConfigInvalid, "legs must share the underlying")
+                    .with_context("underlying", root)
+                    .with_context("other", &leg.root),
+            );
+        }
+        for (i, contract) in contracts.iter().enumerate() {
+            if contracts[..i].contains(contract) {
+                return Err(Error::new(ErrorKind::ConfigInvalid, "duplicate leg")
+                    .with_context("symbol", contract.to_string()));
+            }
+        }
+
+        self.validate_strategy(&contracts)
+    }
+
+    fn validate_strategy(&self, contracts: &[&OccSymbol]) -> Result<()> {
+        let invalid = |reason: &str| {
+            Error::new(
+                ErrorKind::ConfigInvalid,
+                format!("invalid {:?}: {reason}", self.strategy),
+            )
+        };
+        let buying =
+            |leg: &OptionLeg| matches!(leg.action, OptionAction::BuyToOpen | OptionAction::BuyToClose);
+        let same_expiry = contracts.iter().all(|c| c.expiry == contracts[0].expiry);
+        let same_ratio = self.legs.iter().all(|leg| leg.ratio == self.legs[0].ratio);
+        let legs = self.legs.len();
+
+        match self.strategy {
+            SpreadStrategy::Custom => Ok(()),
+            SpreadStrategy::Vertical | SpreadStrategy::Calendar => {
+                if legs != 2 {
+                    return Err(invalid("needs 2 legs"));
+                }
+                let (a, b) = (contracts[0], contracts[1]);
+                if a.right != b.right {
+                    return Err(invalid("legs must be both calls or both puts"));
+                }
+                if buying(&self.legs[0]) == buying(&self.legs[1]) || !same_ratio {
+                    return Err(invalid("must buy one leg and sell the other"));
+                }
+                match self.strategy {
+                    SpreadStrategy::Vertical if a.expiry != b.expiry => Err(invalid("expirations differ")),
+                    SpreadStrategy::Vertical if a.strike == b.strike => Err(invalid("strikes must differ")),
+                    SpreadStrategy::Calendar if a.strike != b.strike => Err(invalid("strikes differ")),
+                    SpreadStrategy::Calendar if a.expiry == b.expiry => {
+                        Err(invalid("expirations must differ"))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            SpreadStrategy::Straddle | SpreadStrategy::Strangle => {
+                if legs != 2 {
+                    return Err(invalid("needs 2 legs"));
+                }
+                let (a, b) = (contracts[0], contracts[1]);
+                if a.right == b.right {
+                    return Err(invalid("needs a call and a put"));
+                }
+                if !same_expiry {
+                    return Err(invalid("expirations differ"));
+                }
+                if self.legs[0].action != self.legs[1].action || !same_ratio {
+                    return Err(invalid("both legs must be bought or sold together"));
+                }
+                match self.strategy {
+                    SpreadStrategy::Straddle if a.strike != b.strike => Err(invalid("strikes differ")),
+                    SpreadStrategy::Strangle if a.strike == b.strike => Err(invalid("strikes must differ")),
+                    _ => Ok(()),
+                }
+            }
+            SpreadStrategy::IronCondor => {
+                if legs != 4 {
+                    return Err(invalid("needs 4 legs"));
+                }
+                if !same_expiry || !same_ratio {
+                    return Err(invalid("legs must share expiration and ratio"));
+                }
+                let puts: Vec<_> = (0..4).filter(|i| contracts[*i].is_put()).collect();
+                let calls: Vec<_> = (0..4).filter(|i| contracts[*i].is_call()).collect();
+                if puts.len() != 2 {
+                    return Err(invalid("needs 2 puts and 2 calls"));
+                }
+                let highest_put = puts.iter().map(|i| contracts[*i].strike).max();
+                let lowest_call = calls.iter().map(|i| contracts[*i].strike).min();
+                if highest_put >= lowest_call {
+                    return Err(invalid("put strikes must be below call strikes"));
+                }
+                if [&puts, &calls]
+                    .iter()
+                    .any(|pair| buying(&self.legs[pair[0]]) == buying(&self.legs[pair[1]]))
+                {
+                    return Err(invalid("each side must buy one leg and sell the other"));
+                }
+                Ok(())
+            }
+            SpreadStrategy::Butterfly => {
+                if legs != 3 {
+                    return Err(invalid("needs 3 legs"));
+                }
+                if !same_expiry || contracts.iter().any(|c| c.right != contracts[0].right) {
+                    return Err(invalid("legs must share expiration and type"));
+                }
+                let mut order: Vec<usize> = (0..3).collect();
+                order.sort_by_key(|i| contracts[*i].strike);
+                let [lower, middle, upper] = [order[0], order[1], order[2]];
+                let (low, mid, high) = (
+                    contracts[lower].strike.thousandths(),
+                    contracts[middle].strike.thousandths(),
+                    contracts[upper].strike.thousandths(),
+                );
+                if low == mid || mid - low != high - mid {
+                    return Err(invalid("strikes must be equidistant"));
+                }
+                let wing = self.legs[lower].ratio;
+                if self.legs[upper].ratio != wing || self.legs[middle].ratio != 2 * wing {
+                    return Err(invalid("ratios must be 1:2:1"));
+                }
+                if buying(&self.legs[lower]) != buying(&self.legs[upper])
+                    || buying(&self.legs[lower]) == buying(&self.legs[middle])
+                {
+                    return Err(invalid("the body must be traded against the wings"));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn to_form(&self, account: &str, preview: bool) -> HashMap<&'static str, String> {
+        let (price_type, net_type, limit_price) = match self.price {
+            NetPrice::Market => ("1", None, None),
+            NetPrice::Debit(price) => ("2", Some("D"), Some(price)),
+            NetPrice::Credit(price) => ("2", Some("C"), Some(price)),
+            NetPrice::Even => ("2", Some("E"), Some(Money::from(0))),
+        };
+        let mut form = HashMap::from([
+            ("account", account.to_string()),
+            ("quantity", self.quantity.to_string()),
+            ("price_type", price_type.to_string()),
+            ("duration", self.duration.as_str().to_string()),
+            ("preview", preview.to_string()),
+            ("leg_count", self.legs.len().to_string()),
+        ]);
+        if let (Some(net_type), Some(limit_price)) = (net_type, limit_price) {
+            form.insert("net_type", net_type.to_string());
+            form.insert("limit_price", limit_price.to_string());
+        }
+        for (leg, [symbol, transaction, ratio]) in self.legs.iter().zip(LEG_FIELDS) {
+            form.insert(symbol, leg.symbol.to_string());
+            form.insert(transaction, leg.action.side().as_str().to_string());
+            form.insert(ratio, leg.ratio.to_string());
+        }
+        form
+    }
+}
+
+// ==================== Preview ====================
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub result: Option<PreviewResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewResult {
+    /// Estimated value of the shares, at the limit price if any.
+    pub principal: Money,
+    pub commission: Money,
+    #[serde(default)]
+    pub fee: Money,
+    /// Principal plus commission and fees.
+    pub estimated_total: Money,
+    pub buying_power: Option<Money>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// An order accepted by the preview, the only way to place it.
+#[derive(Debug, Clone)]
+pub struct OrderPreview<O> {
+    order: O,
+    estimate: PreviewResult,
+}
+
+impl<O> OrderPreview<O> {
+    pub(crate) fn new(order: O, estimate: PreviewResult) -> Self {
+        Self { order, estimate }
+    }
+
+    pub fn order(&self) -> &O {
+        &self.order
+    }
+
+    pub fn estimate(&self) -> &PreviewResult {
+        &self.estimate
+    }
+
+    pub fn estimated_cost(&self) -> Money {
+        self.estimate.estimated_total
+    }
+
+    pub fn commission(&self) -> Money {
+        self.estimate.commission
+    }
+}
+
+// ==================== Place Order ====================
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaceOrderResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub result: Option<OrderConfirmation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderConfirmation {
+    pub order_id: String,
+    pub result: String,
+}
+
+// ==================== Order Status ====================
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderState {
+    #[serde(alias = "Pending")]
+    Open,
+    #[serde(rename = "Partial Filled", alias = "Partially Filled")]
+    PartiallyFilled,
+    Filled,
+    #[serde(rename = "Pending Cancel")]
+    PendingCancel,
+    #[serde(alias = "Canceled")]
+    Cancelled,
+    Rejected,
+    Expired,
+    /// Replaced by another order, see [`ReplaceConfirmation`].
+    Replaced,
+    #[serde(other)]
+    Unknown,
+}
+
+impl OrderState {
+    /// The order is still working and can be cancelled or replaced.
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderState::Open | OrderState::PartiallyFilled)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderListResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub items: Option<Vec<OrderStatus>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderStatus {
+    pub order_id: String,
+    pub symbol: String,
+    pub transaction: OrderSide,
+    pub quantity: f64,
+    #[serde(default)]
+    pub executed_quantity: f64,
+    pub avg_fill_price: Option<Money>,
+    pub price_type: String,
+    pub limit_price: Option<Money>,
+    pub stop_price: Option<Money>,
+    pub duration: Duration,
+    pub status: OrderState,
+    pub reject_reason: Option<String>,
+    pub create_time: String,
+    #[serde(default)]
+    pub fills: Vec<Fill>,
+    /// Legs of a multi-leg order, `symbol` is then the underlying.
+    #[serde(default)]
+    pub legs: Vec<OrderLeg>,
+}
+
+impl OrderStatus {
+    pub fn remaining_quantity(&self) -> f64 {
+        (self.quantity - self.executed_quantity).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderLeg {
+    pub symbol: String,
+    pub transaction: OrderSide,
+    pub ratio: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub quantity: f64,
+    pub price: Money,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelOrderResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub result: Option<OrderConfirmation>,
+}
+
+// ==================== Replace Order ====================
+/// Changes to an open order, anything left unset is kept.
+#[derive(Debug, Clone, Default, PartialEq, TypedBuilder)]
+pub struct OrderChange {
+    #[builder(default, setter(strip_option))]
+    pub quantity: Option<u32>,
+    #[builder(default, setter(strip_option))]
+    pub order_type: Option<OrderType>,
+    #[builder(default, setter(strip_option))]
+    pub duration: Option<Duration>,
+}
+
+/// The order replacing an open order, the quantity includes what was already executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplaceOrder {
+    pub original_order_id: String,
+    pub order: StockOrder,
+}
+
+impl ReplaceOrder {
+    pub fn new(original: &OrderStatus, change: OrderChange) -> Result<Self> {
+        if !original.status.is_open() {
+            return Err(Error::new(ErrorKind::ConditionNotMatch, "order is not open")
+                .with_context("order_id", &original.order_id)
+                .with_context("status", format!("{:?}", original.status)));
+        }
+        if !original.legs.is_empty() {
+            return Err(
+                Error::new(ErrorKind::Unsupported, "multi-leg orders cannot be replaced")
+                    .with_context("order_id", &original.order_id),
+            );
+        }
+        // NOTE: `replace_order` takes the stock order form, option orders would be sent as shares
+        if matches!(original.transaction, OrderSide::BuyToOpen | OrderSide::SellToOpen)
+            || original.symbol.parse::<OccSymbol>().is_ok()
+        {
+            return Err(
+                Error::new(ErrorKind::Unsupported, "option orders cannot be replaced")
+                    .with_context("order_id", &original.order_id)
+                    .with_context("symbol", &original.symbol),
+            );
+        }
+        if original.quantity.fract() != 0.0 {
+            return Err(
+                Error::new(ErrorKind::Unsupported, "fractional orders cannot be replaced")
+                    .with_context("order_id", &original.order_id)
+                    .with_context("quantity", original.quantity.to_string()),
+            );
+        }
+        if change == OrderChange::default() {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "nothing to change")
+                .with_context("order_id", &original.order_id));
+        }
+
+        let order_type = match change.order_type {
+            Some(order_type) => order_type,
+            None => {
+                OrderType::from_price_type(&original.price_type, original.limit_price, original.stop_price)
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::Unsupported, "unsupported price type")
+                            .with_context("order_id", &original.order_id)
+                            .with_context("price_type", &original.price_type)
+                    })?
+            }
+        };
+        let quantity = change.quantity.unwrap_or(original.quantity as u32);
+        if f64::from(quantity) <= original.executed_quantity {
+            return Err(Error::new(
+                ErrorKind::ConfigInvalid,
+                "quantity must exceed the executed quantity",
+            )
+            .with_context("order_id", &original.order_id)
+            .with_context("executed_quantity", original.executed_quantity.to_string()));
+        }
+
+        let order = StockOrder {
+            symbol: original.symbol.clone(),
+            side: original.transaction,
+            quantity,
+            order_type,
+            duration: change.duration.unwrap_or(original.duration),
+            instruction: None,
+        };
+        order.validate()?;
+
+        Ok(Self {
+            original_order_id: original.order_id.clone(),
+            order,
+        })
+    }
+
+    pub(crate) fn to_form(&self, account: &str, preview: bool) -> HashMap<&'static str, String> {
+        let mut form = self.order.to_form(account, preview);
+        form.insert("orig_order_id", self.original_order_id.clone());
+        form
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceOrderResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    pub result: Option<ReplaceConfirmation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceConfirmation {
+    /// Id of the new order, the original one is marked as replaced.
+    pub order_id: String,
+    pub original_order_id: String,
+    pub result: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn money(amount: &str) -> Money {
+        amount.parse().unwrap()
+    }
+
+    fn occ(symbol: &str) -> OccSymbol {
+        symbol.parse().unwrap()
+    }
+
+    #[test]
+    fn test_stock_order_form() {
+        let order = StockOrder::builder()
+            .symbol(" aapl ")
+            .side(OrderSide::Buy)
+            .quantity(10)
+            .order_type(OrderType::StopLimit {
+                stop_price: money("150.5"),
+                limit_price: money("151"),
+            })
+            .duration(Duration::GoodTillCanceled)
+            .build();
+        order.validate().unwrap();
+
+        let form = order.to_form("12345678", true);
+        assert_eq!(form["symbol"], "AAPL");
+        assert_eq!(form["transaction"], "B");
+        assert_eq!(form["shares"], "10");
+        assert_eq!(form["price_type"], "4");
+        assert_eq!(form["limit_price"], "151");
+        assert_eq!(form["stop_price"], "150.5");
+        assert_eq!(form["duration"], "1");
+        assert_eq!(form["preview"], "true");
+        assert!(!form.contains_key("instructions"));
+
+        let order = StockOrder::builder()
+            .symbol("MSFT")
+            .side(OrderSide::SellShort)
+            .quantity(1)
+            .order_type(OrderType::Market)
+            .instruction(OrderInstruction::OnClose)
+            .build();
+        let form = order.to_form("12345678", false);
+        assert_eq!(form["price_type"], "1");
+        assert_eq!(form["duration"], "0");
+        assert_eq!(form["instructions"], "5");
+        assert!(!form.contains_key("limit_price"));
+    }
+
+    #[test]
+    fn test_stock_order_validation() {
+        let order = StockOrder::builder()
+            .symbol("AAPL")
+            .side(OrderSide::Sell)
+            .quantity(0)
+            .order_type(OrderType::Market)
+            .build();
+        assert_eq!(order.validate().unwrap_err().kind(), ErrorKind::ConfigInvalid);
+
+        let order = StockOrder {
+            quantity: 1,
+            order_type: OrderType::Limit {
+                limit_price: money("-1"),
+            },
+            ..order
+        };
+        assert_eq!(order.validate().unwrap_err().kind(), ErrorKind::ConfigInvalid);
+
+        let order = StockOrder {
+            order_type: OrderType::Stop {
+                stop_price: money("0"),
+            },
+            ..order
+        };
+        assert_eq!(order.validate().unwrap_err().kind(), ErrorKind::ConfigInvalid);
+    }
+
+    #[test]
+    fn test_preview_deserialization() {
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "result": {
+                "principal": 1510.0,
+                "commission": 0.0,
+                "estimated_total": 1510.0,
+                "buying_power": 192854.39
+            }
+        });
+        let resp: PreviewResponse = serde_json::from_value(json_data).unwrap();
+        let result = resp.result.unwrap();
+        assert_eq!(result.fee, money("0"));
+        assert_eq!(result.buying_power.unwrap().to_string(), "192854.39");
+        assert!(result.warnings.is_empty());
+
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "result": { "order_id": "201907151", "result": "success" }
+        });
+        let resp: PlaceOrderResponse = serde_json::from_value(json_data).unwrap();
+        assert_eq!(resp.result.unwrap().order_id, "201907151");
+    }
+
+    #[test]
+    fn test_order_status_deserialization() {
+        let json_data = json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "items": [
+                {
+                    "order_id": "10001",
+                    "symbol": "AAPL",
+                    "transaction": "B",
+                    "quantity": 10,
+                    "executed_quantity": 4,
+                    "avg_fill_price": 38.45,
+                    "price_type": "2",
+                    "limit_price": 38.5,
+                    "stop_price": null,
+                    "duration": "1",
+                    "status": "Partial Filled",
+                    "reject_reason": null,
+                    "create_time": "2025-08-06 09:31:02",
+                    "fills": [
+                        { "quantity": 4, "price": 38.45, "time": "2025-08-06 09:31:05" }
+                    ]
+                },
+                {
+                    "order_id": "10002",
+                    "symbol": "MSFT",
+                    "transaction": "S",
+                    "quantity": 1,
+                    "avg_fill_price": null,
+                    "price_type": "1",
+                    "limit_price": null,
+                    "stop_price": null,
+                    "duration": "0",
+                    "status": "Routed",
+                    "reject_reason": null,
+                    "create_time": "2025-08-06 09:32:00"
+                }
+            ]
+        });
+        let resp: OrderListResponse = serde_json::from_value(json_data).unwrap();
+        let items = resp.items.unwrap();
+        assert_eq!(items[0].status, OrderState::PartiallyFilled);
+        assert!(items[0].status.is_open());
+        assert_eq!(items[0].remaining_quantity(), 6.0);
+        assert_eq!(items[0].fills.len(), 1);
+        assert_eq!(items[1].status, OrderState::Unknown);
+        assert_eq!(items[1].duration, Duration::Day);
+        assert!(items[1].fills.is_empty());
+    }
+
+    fn open_order() -> OrderStatus {
+        serde_json::from_value(json!({
+            "order_id": "10001",
+            "symbol": "AAPL",
+            "transaction": "B",
+            "quantity": 10,
+            "executed_quantity": 4,
+            "avg_fill_price": 38.45,
+            "price_type": "2",
+            "limit_price": 38.5,
+            "stop_price": null,
+            "duration": "0",
+            "status": "Partial Filled",
+            "reject_reason": null,
+            "create_time": "2025-08-06 09:31:02"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_replace_order() {
+        let original = open_order();
+        let change = OrderChange::builder()
+            .order_type(OrderType::Limit {
+                limit_price: money("38.8"),
+            })
+            .build();
+        let replace = ReplaceOrder::new(&original, change).unwrap();
+        assert_eq!(replace.order.quantity, 10);
+        assert_eq!(replace.order.side, OrderSide::Buy);
+        assert_eq!(replace.order.duration, Duration::Day);
+
+        let form = replace.to_form("12345678", true);
+        assert_eq!(form["orig_order_id"], "10001");
+        assert_eq!(form["limit_price"], "38.8");
+
+        let change = OrderChange::builder()
+            .quantity(20)
+            .duration(Duration::GoodTillCanceled)
+            .build();
+        let replace = ReplaceOrder::new(&original, change).unwrap();
+        assert_eq!(
+            replace.order.order_type,
+            OrderType::Limit {
+                limit_price: money("38.5")
+            }
+        );
+        assert_eq!(replace.order.quantity, 20);
+
+        let err = ReplaceOrder::new(&original, OrderChange::default()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+        let err = ReplaceOrder::new(&original, OrderChange::builder().quantity(4).build()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+
+        let filled = OrderStatus {
+            status: OrderState::Filled,
+            ..original
+        };
+        let err = ReplaceOrder::new(&filled, OrderChange::builder().quantity(20).build()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConditionNotMatch);
+    }
+
+    #[test]
+    fn test_replace_unsupported_order() {
+        let change = || OrderChange::builder().quantity(20).build();
+
+        let option = OrderStatus {
+            symbol: "ABCD260116C00003000".to_string(),
+            transaction: OrderSide::Sell,
+            ..open_order()
+        };
+        let err = ReplaceOrder::new(&option, change()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        let opening = OrderStatus {
+            transaction: OrderSide::BuyToOpen,
+            ..open_order()
+        };
+        let err = ReplaceOrder::new(&opening, change()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+
+        let fractional = OrderStatus {
+            quantity: 10.5,
+            ..open_order()
+        };
+        let err = ReplaceOrder::new(&fractional, change()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_option_order() {
+        let order = OptionOrder::builder()
+            .symbol(occ("abcd260116c00003000"))
+            .action(OptionAction::SellToClose)
+            .contracts(2)
+            .order_type(OrderType::Limit {
+                limit_price: money("0.95"),
+            })
+            .build();
+        order.validate().unwrap();
+
+        let form = order.to_form("12345678", true);
+        assert_eq!(form["symbol"], "ABCD260116C00003000");
+        assert_eq!(form["transaction"], "S");
+        assert_eq!(form["contracts"], "2");
+        assert!(!form.contains_key("shares"));
+        assert_eq!(OptionAction::BuyToOpen.side().as_str(), "BO");
+        assert_eq!(OptionAction::BuyToOpen.required_option_level(), Some(2));
+        assert_eq!(OptionAction::SellToOpen.required_option_level(), None);
+
+        let order = OptionOrder {
+            contracts: 0,
+            ..order.clone()
+        };
+        assert_eq!(order.validate().unwrap_err().kind(), ErrorKind::ConfigInvalid);
+
+        let stock = StockOrder::builder()
+            .symbol("AAPL")
+            .side(OrderSide::BuyToOpen)
+            .quantity(1)
+            .order_type(OrderType::Market)
+            .build();
+        assert_eq!(stock.validate().unwrap_err().kind(), ErrorKind::ConfigInvalid);
+    }
+
+    #[test]
+    fn test_multi_leg_strategies() {
+        let quantity = 1;
+        let debit = NetPrice::Debit(money("1.2"));
+        let orders = [
+            MultiLegOrder::vertical(
+                occ("SPY250919C00600000"),
+                occ("SPY250919C00610000"),
+                quantity,
+                debit,
+            ),
+            MultiLegOrder::calendar(
+                occ("SPY250919P00600000"),
+                occ("SPY251017P00600000"),
+                quantity,
+                debit,
+            ),
+            MultiLegOrder::straddle(
+                occ("SPY250919C00600000"),
+                occ("SPY250919P00600000"),
+                quantity,
+                debit,
+            ),
+            MultiLegOrder::strangle(
+                occ("SPY250919C00610000"),
+                occ("SPY250919P00590000"),
+                quantity,
+                debit,
+            ),
+            MultiLegOrder::iron_condor(
+                occ("SPY250919P00580000"),
+                occ("SPY250919P00590000"),
+                occ("SPY250919C00610000"),
+                occ("SPY250919C00620000"),
+                quantity,
+                NetPrice::Credit(money("2.1")),
+            ),
+            MultiLegOrder::butterfly(
+                occ("SPY250919C00590000"),
+                occ("SPY250919C00600000"),
+                occ("SPY250919C00610000"),
+                quantity,
+                debit,
+            ),
+        ];
+        for order in orders {
+            order.validate().unwrap();
+            order.clone().short().validate().unwrap();
+            order.clone().closing().validate().unwrap();
+        }
+
+        let straddle =
+            MultiLegOrder::straddle(occ("SPY250919C00600000"), occ("SPY250919P00600000"), 2, debit);
+        assert_eq!(straddle.required_option_level(), 2);
+        let short = straddle.clone().short();
+        assert_eq!(short.price, NetPrice::Credit(money("1.2")));
+        assert_eq!(short.required_option_level(), 3);
+        assert_eq!(straddle.closing().required_option_level(), 1);
+    }
+
+    #[test]
+    fn test_multi_leg_validation() {
+        let debit = NetPrice::Debit(money("1.2"));
+        let invalid = [
+            // Different underlyings
+            MultiLegOrder::vertical(occ("SPY250919C00600000"), occ("QQQ250919C00610000"), 1, debit),
+            // Verticals share the expiration
+            MultiLegOrder::vertical(occ("SPY250919C00600000"), occ("SPY251017C00610000"), 1, debit),
+            MultiLegOrder::vertical(occ("SPY250919C00600000"), occ("SPY250919P00610000"), 1, debit),
+            MultiLegOrder::calendar(occ("SPY250919P00600000"), occ("SPY251017P00610000"), 1, debit),
+            MultiLegOrder::straddle(occ("SPY250919C00600000"), occ("SPY250919P00610000"), 1, debit),
+            MultiLegOrder::strangle(occ("SPY250919C00600000"), occ("SPY250919C00610000"), 1, debit),
+            MultiLegOrder::iron_condor(
+                occ("SPY250919P00580000"),
+                occ("SPY250919P00615000"),
+                occ("SPY250919C00610000"),
+                occ("SPY250919C00620000"),
+                1,
+                debit,
+            ),
+            MultiLegOrder::butterfly(
+                occ("SPY250919C00590000"),
+                occ("SPY250919C00600000"),
+                occ("SPY250919C00620000"),
+                1,
+                debit,
+            ),
+            MultiLegOrder::vertical(occ("SPY250919C00600000"), occ("SPY250919C00610000"), 0, debit),
+            MultiLegOrder::vertical(
+                occ("SPY250919C00600000"),
+                occ("SPY250919C00610000"),
+                1,
+                NetPrice::Debit(money("0")),
+            ),
+            MultiLegOrder::vertical(occ("SPY250919C00600000"), occ("SPY250919C00600000"), 1, debit),
+            MultiLegOrder::new(
+                vec![OptionLeg::new(
+                    occ("SPY250919C00600000"),
+                    OptionAction::BuyToOpen,
+                    1,
+                )],
+                1,
+                debit,
+            ),
+        ];
+        for order in invalid {
+            let err = order.validate().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ConfigInvalid, "{order:?}");
+        }
+
+        let legs = (0..5)
+            .map(|i| OptionLeg::new(occ(&format!("SPY250919C0060{i}000")), OptionAction::BuyToOpen, 1))
+            .collect();
+        let err = MultiLegOrder::new(legs, 1, debit).validate().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+    }
+
+    #[test]
+    fn test_multi_leg_form() {
+        let order = MultiLegOrder::butterfly(
+            occ("SPY250919C00590000"),
+            occ("SPY250919C00600000"),
+            occ("SPY250919C00610000"),
+            3,
+            NetPrice::Debit(money("1.05")),
+        )
+        .closing();
+        let form = order.to_form("12345678", false);
+        assert_eq!(form["leg_count"], "3");
+        assert_eq!(form["quantity"], "3");
+        assert_eq!(form["net_type"], "C");
+        assert_eq!(form["limit_price"], "1.05");
+        assert_eq!(form["transaction1"], "S");
+        assert_eq!(form["transaction2"], "BC");
+        assert_eq!(form["ratio2"], "2");
+        assert_eq!(form["symbol3"], "SPY250919C00610000");
+        assert!(!form.contains_key("symbol4"));
+    }
+}
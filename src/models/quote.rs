@@ -1,8 +1,15 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::money::Money;
+use crate::models::option::{OccSymbol, Strike};
+use crate::models::security::{SecurityType, Symbol};
 use chrono::serde::ts_milliseconds::deserialize as from_milli_ts;
 use chrono::{DateTime, Utc};
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
 
 // ==================== Market Time ====================
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,30 +42,30 @@ pub struct SingleQuoteResponse {
     pub result: Option<QuoteResult>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum QuoteResult {
     Stock(StockQuote),
     Option(OptionQuote),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OptionQuote {
-    pub symbol: String,
-    pub sec_type: u8,
+    pub symbol: OccSymbol,
+    pub sec_type: SecurityType,
     pub underlying_symbol: String,
     pub class: String,
     pub tick: String,
-    pub strike_price: f64,
+    pub strike_price: Strike,
     pub exp_date: String,
-    pub bid: f64,
+    pub bid: Money,
     pub bid_size: u32,
-    pub ask: f64,
+    pub ask: Money,
     pub ask_size: u32,
-    pub last: f64,
-    pub change: f64,
-    pub high: f64,
-    pub low: f64,
+    pub last: Money,
+    pub change: Money,
+    pub high: Money,
+    pub low: Money,
     pub change_color: String,
     pub vol: u32,
     pub quote_time: String,
@@ -67,32 +74,32 @@ pub struct OptionQuote {
     pub shares: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StockQuote {
     pub symbol: String,
-    pub sec_type: u8,
+    pub sec_type: SecurityType,
     pub tick: String,
-    pub bid: f64,
+    pub bid: Money,
     pub bid_size: u32,
-    pub ask: f64,
+    pub ask: Money,
     pub ask_size: u32,
-    pub last: f64,
-    pub change: f64,
-    pub high: f64,
-    pub low: f64,
+    pub last: Money,
+    pub change: Money,
+    pub high: Money,
+    pub low: Money,
     pub bid_mmid: String,
     pub ask_mmid: String,
     pub last_mmid: String,
     pub last_size: u32,
     pub change_color: String,
     pub vol: u64,
-    pub today_close: f64,
-    pub prev_close: f64,
+    pub today_close: Money,
+    pub prev_close: Money,
     pub show_close: String,
     pub change_percent: f64,
     pub margin_long_req: f64,
     pub margin_short_req: f64,
-    pub open: f64,
+    pub open: Money,
     pub quote_time: String,
     pub last_trade_time: String,
     pub company_name: String,
@@ -107,6 +114,186 @@ pub struct StockQuote {
     pub shares: u64,
 }
 
+// ==================== Batch Quotes ====================
+/// Result of [`FtAccount::get_quotes`](crate::account::FtAccount::get_quotes), in request order.
+#[derive(Debug)]
+pub struct QuoteBatch {
+    pub quotes: Vec<(Symbol, Result<QuoteResult>)>,
+    pub stats: QuoteStats,
+}
+
+impl QuoteBatch {
+    pub(crate) fn new(quotes: Vec<(Symbol, Result<QuoteResult>, Duration)>, elapsed: Duration) -> Self {
+        let latencies: Vec<Duration> = quotes.iter().map(|(.., latency)| *latency).collect();
+        let succeeded = quotes.iter().filter(|(_, result, _)| result.is_ok()).count();
+        let stats = QuoteStats {
+            requested: quotes.len(),
+            succeeded,
+            failed: quotes.len() - succeeded,
+            elapsed,
+            min_latency: latencies.iter().min().copied().unwrap_or_default(),
+            max_latency: latencies.iter().max().copied().unwrap_or_default(),
+            mean_latency: latencies
+                .iter()
+                .sum::<Duration>()
+                .checked_div(latencies.len() as u32)
+                .unwrap_or_default(),
+        };
+        let quotes = quotes
+            .into_iter()
+            .map(|(symbol, result, _)| (symbol, result))
+            .collect();
+        Self { quotes, stats }
+    }
+
+    pub fn get(&self, symbol: &Symbol) -> Option<&Result<QuoteResult>> {
+        self.quotes
+            .iter()
+            .find(|(s, _)| s == symbol)
+            .map(|(_, result)| result)
+    }
+
+    /// Quotes that were fetched, skipping the failed symbols.
+    pub fn successes(&self) -> impl Iterator<Item = (&Symbol, &QuoteResult)> {
+        self.quotes
+            .iter()
+            .filter_map(|(symbol, result)| result.as_ref().ok().map(|quote| (symbol, quote)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QuoteStats {
+    /// Distinct symbols requested.
+    pub requested: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Wall time of the whole batch.
+    pub elapsed: Duration,
+    pub min_latency: Duration,
+    pub max_latency: Duration,
+    pub mean_latency: Duration,
+}
+
+// ==================== OHLC Parameters ====================
+/// Time span of [`FtAccount::get_stock_ohlc`](crate::account::FtAccount::get_stock_ohlc).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OhlcRange {
+    #[serde(rename = "1h")]
+    OneHour,
+    #[serde(rename = "1d")]
+    OneDay,
+    /// Last 24 hours, including the overnight session.
+    #[serde(rename = "24h")]
+    TwentyFourHours,
+    #[serde(rename = "1w")]
+    OneWeek,
+    #[serde(rename = "1m")]
+    OneMonth,
+    #[serde(rename = "3m")]
+    ThreeMonths,
+    #[serde(rename = "1y")]
+    OneYear,
+    #[serde(rename = "5y")]
+    FiveYears,
+    #[serde(rename = "ytd")]
+    YearToDate,
+    #[serde(rename = "all")]
+    All,
+}
+
+impl OhlcRange {
+    pub const ALL: [OhlcRange; 10] = [
+        OhlcRange::OneHour,
+        OhlcRange::OneDay,
+        OhlcRange::TwentyFourHours,
+        OhlcRange::OneWeek,
+        OhlcRange::OneMonth,
+        OhlcRange::ThreeMonths,
+        OhlcRange::OneYear,
+        OhlcRange::FiveYears,
+        OhlcRange::YearToDate,
+        OhlcRange::All,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OhlcRange::OneHour => "1h",
+            OhlcRange::OneDay => "1d",
+            OhlcRange::TwentyFourHours => "24h",
+            OhlcRange::OneWeek => "1w",
+            OhlcRange::OneMonth => "1m",
+            OhlcRange::ThreeMonths => "3m",
+            OhlcRange::OneYear => "1y",
+            OhlcRange::FiveYears => "5y",
+            OhlcRange::YearToDate => "ytd",
+            OhlcRange::All => "all",
+        }
+    }
+}
+
+impl fmt::Display for OhlcRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OhlcRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let range = s.trim().to_ascii_lowercase();
+        OhlcRange::ALL
+            .into_iter()
+            .find(|r| r.as_str() == range)
+            .ok_or_else(|| {
+                Error::new(ErrorKind::ConfigInvalid, "invalid ohlc range")
+                    .with_context("range", s)
+                    .with_context("expected", "1h, 1d, 24h, 1w, 1m, 3m, 1y, 5y, ytd, all")
+            })
+    }
+}
+
+/// Number of bars per symbol for [`FtAccount::get_stocks_mohlc`](crate::account::FtAccount::get_stocks_mohlc),
+/// from 1 to [`Resolution::MAX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Resolution(u8);
+
+impl Resolution {
+    pub const MAX: u8 = 50;
+
+    pub fn new(resolution: u8) -> Result<Self> {
+        if !(1..=Self::MAX).contains(&resolution) {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "resolution out of range")
+                .with_context("resolution", resolution.to_string())
+                .with_context("max", Self::MAX.to_string()));
+        }
+        Ok(Resolution(resolution))
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for Resolution {
+    type Error = Error;
+
+    fn try_from(resolution: u8) -> Result<Self> {
+        Resolution::new(resolution)
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Symbols sent per multi-symbol OHLC request by default, see
+/// [`FtAccount::with_mohlc_chunk_size`](crate::account::FtAccount::with_mohlc_chunk_size).
+// NOTE: the endpoint's real limit is neither documented nor confirmed, this is a conservative guess
+pub const DEFAULT_MOHLC_CHUNK_SIZE: usize = 20;
+
 // ==================== Stocks MOHLC ====================
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MohlcResponse {
@@ -122,7 +309,7 @@ pub struct MohlcResponse {
 pub struct StockOhlc {
     pub ohlc: Vec<OhlcEntry>,
     pub vol: Vec<VolEntry>,
-    pub prev_close: Option<f64>,
+    pub prev_close: Option<Money>,
     pub range: Option<String>,
     pub symbol: Option<String>,
 }
@@ -130,10 +317,10 @@ pub struct StockOhlc {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OhlcEntry(
     #[serde(deserialize_with = "from_milli_ts")] pub DateTime<Utc>,
-    pub f64,                           // open
-    pub f64,                           // high
-    pub f64,                           // low
-    pub f64,                           // close
+    pub Money,                         // open
+    pub Money,                         // high
+    pub Money,                         // low
+    pub Money,                         // close
     #[serde(default)] pub Option<u64>, // NOTE: volume only exists in OhlcResponse
 );
 
@@ -159,6 +346,23 @@ mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_ohlc_parameters() {
+        for range in OhlcRange::ALL {
+            assert_eq!(range.as_str().parse::<OhlcRange>().unwrap(), range);
+            assert_eq!(serde_json::to_value(range).unwrap(), range.as_str());
+        }
+        assert_eq!("YTD".parse::<OhlcRange>().unwrap(), OhlcRange::YearToDate);
+        let err = "2d".parse::<OhlcRange>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+
+        assert_eq!(Resolution::new(50).unwrap().get(), 50);
+        for resolution in [0, 51] {
+            let err = Resolution::try_from(resolution).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+        }
+    }
+
     #[test]
     fn test_market_time_response() {
         let json_data = json!({
@@ -229,7 +433,7 @@ mod tests {
         match response.result.unwrap() {
             QuoteResult::Stock(stock) => {
                 assert_eq!(stock.symbol, "HIMS");
-                assert_eq!(stock.sec_type, 1);
+                assert_eq!(stock.sec_type, SecurityType::Equity);
                 assert_eq!(stock.exchange, "NYSE");
             }
             _ => panic!("Expected StockResult"),
@@ -271,8 +475,10 @@ mod tests {
 
         match response.result.unwrap() {
             QuoteResult::Option(option) => {
-                assert_eq!(option.symbol, "OSCR270115C00010000");
-                assert_eq!(option.sec_type, 2);
+                assert_eq!(option.symbol.to_string(), "OSCR270115C00010000");
+                assert_eq!(option.symbol.root, option.underlying_symbol);
+                assert_eq!(option.strike_price, option.symbol.strike);
+                assert_eq!(option.sec_type, SecurityType::Option);
                 assert_eq!(option.underlying_symbol, "OSCR");
                 assert_eq!(option.class, "CALL");
                 assert_eq!(option.exchange, "OPRA");
@@ -457,7 +663,7 @@ mod tests {
             let xyz_ohlc = &result["XYZ"];
             assert_eq!(xyz_ohlc.ohlc.len(), 4);
             assert_eq!(xyz_ohlc.vol.len(), 4);
-            assert_eq!(xyz_ohlc.prev_close.unwrap(), 40.34);
+            assert_eq!(xyz_ohlc.prev_close.unwrap().to_string(), "40.34");
         } else {
             panic!("Expected result to be present");
         }
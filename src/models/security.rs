@@ -0,0 +1,173 @@
+use crate::error::{Error, ErrorKind};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A ticker symbol, trimmed and uppercased, e.g. `BRK.B`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Symbol(String);
+
+impl Symbol {
+    // NOTE: commas separate symbols in list requests, so they can't be part of one
+    pub fn new(symbol: &str) -> crate::error::Result<Self> {
+        let trimmed = symbol.trim();
+        if trimmed.is_empty() || !trimmed.chars().all(|c| c.is_ascii_graphic() && c != ',') {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "invalid symbol").with_context("symbol", symbol));
+        }
+        Ok(Symbol(trimmed.to_ascii_uppercase()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = Error;
+
+    fn from_str(s: &str) -> crate::error::Result<Self> {
+        Symbol::new(s)
+    }
+}
+
+impl TryFrom<&str> for Symbol {
+    type Error = Error;
+
+    fn try_from(symbol: &str) -> crate::error::Result<Self> {
+        Symbol::new(symbol)
+    }
+}
+
+impl TryFrom<String> for Symbol {
+    type Error = Error;
+
+    fn try_from(symbol: String) -> crate::error::Result<Self> {
+        Symbol::new(&symbol)
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.0
+    }
+}
+
+/// Kind of security, sent and received as the numeric `sec_type` code.
+// NOTE: codes without a variant are kept in `Other` so they round-trip unchanged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SecurityType {
+    Equity,
+    Option,
+    MutualFund,
+    FixedIncome,
+    Etf,
+    Other(i64),
+}
+
+impl SecurityType {
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            1 => SecurityType::Equity,
+            2 => SecurityType::Option,
+            3 => SecurityType::MutualFund,
+            4 => SecurityType::FixedIncome,
+            5 => SecurityType::Etf,
+            code => SecurityType::Other(code),
+        }
+    }
+
+    pub fn code(&self) -> i64 {
+        match self {
+            SecurityType::Equity => 1,
+            SecurityType::Option => 2,
+            SecurityType::MutualFund => 3,
+            SecurityType::FixedIncome => 4,
+            SecurityType::Etf => 5,
+            SecurityType::Other(code) => *code,
+        }
+    }
+
+    pub fn is_option(&self) -> bool {
+        *self == SecurityType::Option
+    }
+}
+
+impl From<i64> for SecurityType {
+    fn from(code: i64) -> Self {
+        SecurityType::from_code(code)
+    }
+}
+
+impl From<SecurityType> for i64 {
+    fn from(sec_type: SecurityType) -> Self {
+        sec_type.code()
+    }
+}
+
+impl fmt::Display for SecurityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+impl Serialize for SecurityType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for SecurityType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(SecurityType::from_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_security_type_serde() {
+        for (code, sec_type) in [
+            (1, SecurityType::Equity),
+            (2, SecurityType::Option),
+            (3, SecurityType::MutualFund),
+            (4, SecurityType::FixedIncome),
+            (5, SecurityType::Etf),
+            (0, SecurityType::Other(0)),
+            (9, SecurityType::Other(9)),
+        ] {
+            let parsed: SecurityType = serde_json::from_value(json!(code)).unwrap();
+            assert_eq!(parsed, sec_type);
+            assert_eq!(serde_json::to_value(parsed).unwrap(), json!(code));
+        }
+        assert_eq!(SecurityType::Option.to_string(), "2");
+        assert!(serde_json::from_value::<SecurityType>(json!("1")).is_err());
+    }
+
+    #[test]
+    fn test_symbol() {
+        assert_eq!(Symbol::new(" brk.b ").unwrap().as_str(), "BRK.B");
+        for symbol in ["", "  ", "AAPL,MSFT", "BRK B", "ÄPL"] {
+            let err = Symbol::new(symbol).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ConfigInvalid, "{symbol}");
+        }
+        let symbol: Symbol = serde_json::from_value(json!("msft")).unwrap();
+        assert_eq!(serde_json::to_value(&symbol).unwrap(), json!("MSFT"));
+        assert!(serde_json::from_value::<Symbol>(json!("A,B")).is_err());
+    }
+}
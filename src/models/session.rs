@@ -21,12 +21,39 @@ pub(crate) struct ErrorResponse {
 
 #[derive(Serialize, Deserialize, TypedBuilder)]
 pub(crate) struct LoginOtpRequest {
-    #[serde(rename = "optCode")]
+    #[serde(rename = "otpCode")]
     pub otp_code: String,
+    #[serde(rename = "verificationSid")]
+    pub verification_sid: String,
     pub remember_for: i64,
     pub t_token: String,
 }
 
+#[derive(Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct LoginPinRequest {
+    pub pin: String,
+    pub remember_for: i64,
+    pub t_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, TypedBuilder)]
+pub(crate) struct OtpCodeRequest {
+    #[serde(rename = "recipientId")]
+    pub recipient_id: i64,
+    pub t_token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct OtpCodeResponse {
+    #[serde(rename = "statusCode")]
+    #[serde(with = "http_serde::status_code")]
+    pub status_code: StatusCode,
+    pub error: String,
+    pub message: String,
+    #[serde(rename = "verificationSid")]
+    pub verification_sid: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(untagged)]
 pub(crate) enum LoginResponse {
@@ -36,14 +63,14 @@ pub(crate) enum LoginResponse {
 }
 
 // ================= LoginWithOtpResponse =================
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fallback {
     pub strategy: String,
     #[serde(rename = "pinDisabled")]
     pub pin_disabled: bool,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OtpOptions {
     #[serde(rename = "recipientId")]
     pub recipient_id: i64,
@@ -95,6 +122,7 @@ pub(crate) struct LoginVerifiedResponse {
     pub nls_quote: bool,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize)]
 pub(crate) struct TcResponse {
     #[serde(rename = "Authorization")]
@@ -139,7 +167,7 @@ mod tests {
             assert_eq!(otp_response.error, "");
             assert_eq!(otp_response.message, "Normal");
             assert_eq!(otp_response.t_token, "1f4b31f064cb12366e397192db86fcbf");
-            assert_eq!(otp_response.mfa, false);
+            assert!(!otp_response.mfa);
             assert_eq!(otp_response.otp.len(), 2);
             assert_eq!(otp_response.fallback.strategy, "pin");
             assert!(otp_response.fallback.pin_disabled);
@@ -148,6 +176,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_otp_code_deserialization() {
+        let json_data = json!(
+        {
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "verificationSid": "VE8a1d5a1e7e3c4ac1b1b7e3a1f5c2d9e0"
+        });
+        let response: OtpCodeResponse = serde_json::from_value(json_data).unwrap();
+        assert_eq!(response.status_code, 200);
+        assert_eq!(
+            response.verification_sid.unwrap(),
+            "VE8a1d5a1e7e3c4ac1b1b7e3a1f5c2d9e0"
+        );
+    }
+
     #[test]
     fn test_longin_mfa_deserialization() {
         let json_data = json!(
@@ -165,7 +210,7 @@ mod tests {
             assert_eq!(init_response.error, "");
             assert_eq!(init_response.message, "Normal");
             assert_eq!(init_response.t_token, "073e8a2ae7331c32e8b0c12004248e00");
-            assert_eq!(init_response.mfa, true);
+            assert!(init_response.mfa);
         } else {
             panic!("Expected LoginResponse::Init");
         }
@@ -200,7 +245,7 @@ mod tests {
                 verify_response.ftat,
                 "3B3812FC07A431A911A193C5CA1D8A63B184D6E88FA3DAC4CE4DA7D703DBC9C0"
             );
-            assert_eq!(verify_response.realtime_quote, true);
+            assert!(verify_response.realtime_quote);
         } else {
             panic!("Expected LoginResponse::Verify");
         }
@@ -1,3 +1,5 @@
+use crate::models::money::Money;
+use crate::models::security::SecurityType;
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
@@ -38,29 +40,29 @@ pub struct WatchListQuote {
     pub list_items: Vec<ItemQuote>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ItemQuote {
     pub watchlist_id: i64,
-    pub sec_type: i64,
+    pub sec_type: SecurityType,
     pub symbol: String,
     pub quantity: i64,
-    pub last: f64,
-    pub bid: f64,
-    pub ask: f64,
+    pub last: Money,
+    pub bid: Money,
+    pub ask: Money,
     pub vol: i64,
-    pub change: f64,
+    pub change: Money,
     pub change_percent: f64,
-    pub day_gain_amount: i64,
-    pub unit_cost: i64,
-    pub cost: i64,
-    pub gain_amount: i64,
-    pub gain_percent: i64,
+    pub day_gain_amount: Money,
+    pub unit_cost: Money,
+    pub cost: Money,
+    pub gain_amount: Money,
+    pub gain_percent: f64,
     pub bidsize: i64,
     pub asksize: i64,
-    pub high: f64,
-    pub low: f64,
-    pub close_price: f64,
-    pub open_price: f64,
+    pub high: Money,
+    pub low: Money,
+    pub close_price: Money,
+    pub open_price: Money,
     pub update_time: String,
 }
 
@@ -119,4 +121,49 @@ mod tests {
         assert_eq!(result.error, "");
         assert_eq!(result.message, "Normal");
     }
+
+    #[test]
+    fn test_watchlist_quote_deserialization() {
+        let json_data = json!(
+        {
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "result": {
+                "list_id": 1001,
+                "name": "Default",
+                "list_items": [{
+                    "watchlist_id": 5001,
+                    "sec_type": 1,
+                    "symbol": "AAPL",
+                    "quantity": 10,
+                    "last": 229.35,
+                    "bid": 229.3,
+                    "ask": 229.4,
+                    "vol": 42_000_000,
+                    "change": 1.25,
+                    "change_percent": 0.55,
+                    "day_gain_amount": 12.5,
+                    "unit_cost": 180.25,
+                    "cost": 1802.5,
+                    "gain_amount": 491.0,
+                    "gain_percent": 27.24,
+                    "bidsize": 3,
+                    "asksize": 5,
+                    "high": 230.1,
+                    "low": 227.8,
+                    "close_price": 228.1,
+                    "open_price": 228.5,
+                    "update_time": "03:59:59 pm"
+                }]
+            }
+        });
+        let result: WatchListQuoteResponse = serde_json::from_value(json_data).unwrap();
+        let item = &result.result.unwrap().list_items[0];
+        assert_eq!(item.sec_type, SecurityType::Equity);
+        assert_eq!(item.unit_cost.to_string(), "180.25");
+        assert_eq!(item.cost.to_string(), "1802.5");
+        assert_eq!(item.gain_amount.to_string(), "491");
+        assert_eq!(item.gain_percent, 27.24);
+    }
 }
@@ -1,17 +1,24 @@
+use crate::endpoint::Endpoint;
 use crate::error::{Error, ErrorKind, Result};
 use crate::models::account::AccountList;
-use crate::models::session::{LoginMfaRequest, LoginResponse};
-use crate::url::{ACCESS_TOKEN, account_list, login, verify_pin};
+use crate::models::session::{
+    LoginMfaRequest, LoginOtpRequest, LoginPinRequest, LoginResponse, OtpCodeRequest, OtpCodeResponse,
+    OtpOptions,
+};
+use crate::store::{CredentialStore, StoredCreds, load_or_warn};
+use crate::totp::MfaSecret;
+use crate::url::{account_list, login, request_code, verify_pin};
 use crate::utils::*;
-use async_recursion::async_recursion;
 use axum::http::{HeaderMap, HeaderValue};
 use derive_more::From;
-use reqwest::Client as HttpClient;
+use reqwest::{Client as HttpClient, RequestBuilder};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 use zeroize::Zeroize;
 
-#[derive(Clone, Debug, From)]
+#[derive(Clone, Debug, From, Serialize, Deserialize)]
 pub(crate) struct FirstTradeUsername(String);
 
 impl FirstTradeUsername {
@@ -20,7 +27,7 @@ impl FirstTradeUsername {
     }
 }
 
-#[derive(Clone, Debug, From, Zeroize)]
+#[derive(Clone, Debug, Default, From, Zeroize)]
 pub(crate) struct FirstTradePassword(String);
 
 impl FirstTradePassword {
@@ -29,7 +36,7 @@ impl FirstTradePassword {
     }
 }
 
-#[derive(Clone, Debug, From, Zeroize)]
+#[derive(Clone, Debug, From, Serialize, Deserialize, Zeroize)]
 pub(crate) struct FirstTradeAccessToken(String);
 
 impl FirstTradeAccessToken {
@@ -42,7 +49,7 @@ impl FirstTradeAccessToken {
     }
 }
 
-#[derive(Clone, Debug, From, Zeroize)]
+#[derive(Clone, Debug, From, Serialize, Deserialize, Zeroize)]
 pub(crate) struct SessionId(String);
 
 impl SessionId {
@@ -64,12 +71,19 @@ impl AccountId {
     }
 }
 
-#[derive(Clone)]
+/// Credentials of a verified session.
+///
+/// NOTE: the serialized form only carries the username, `ftat` and `sid`, never the password.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct FtCreds {
     pub(crate) username: FirstTradeUsername,
+    #[serde(skip)]
     pub(crate) password: FirstTradePassword,
     pub(crate) ftat: FirstTradeAccessToken,
     pub(crate) sid: SessionId,
+    // NOTE: never serialized, only kept in memory to re-login without a human
+    #[serde(skip)]
+    pub(crate) mfa_secret: Option<MfaSecret>,
 }
 
 impl Debug for FtCreds {
@@ -109,8 +123,11 @@ pub struct FtSessionConfig {
     phone: Option<String>,
     pin: Option<String>,
     mfa_code: Option<String>,
+    mfa_secret: Option<MfaSecret>,
     ftat: Option<String>,
     client: Option<HttpClient>,
+    endpoint: Endpoint,
+    credential_store: Option<Arc<dyn CredentialStore>>,
 }
 
 impl Debug for FtSessionConfig {
@@ -122,8 +139,11 @@ impl Debug for FtSessionConfig {
             .field("pin", &self.pin.as_ref().map(|_| "***"))
             .field("email", &self.email.as_ref().map(|_| "***"))
             .field("phone", &self.phone.as_ref().map(|_| "***"))
-            .field("mfa_secret", &self.mfa_code.as_ref().map(|_| "***"))
+            .field("mfa_code", &self.mfa_code.as_ref().map(|_| "***"))
+            .field("mfa_secret", &self.mfa_secret.as_ref().map(|_| "***"))
             .field("access_token", &self.ftat.as_ref().map(|_| "***"))
+            .field("endpoint", &self.endpoint)
+            .field("credential_store", &self.credential_store.is_some())
             .finish()
     }
 }
@@ -138,13 +158,27 @@ impl Default for FtSessionConfig {
             phone: None,
             pin: None,
             mfa_code: None,
+            mfa_secret: None,
             ftat: None,
             client: None,
+            endpoint: Endpoint::default(),
+            credential_store: None,
         }
     }
 }
 
 impl FtSessionConfig {
+    /// Config for logging in again as the owner of `creds`, carrying over the MFA secret if any.
+    pub(crate) fn from_creds(creds: &FtCreds, endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            username: Some(String::from(creds.username.as_str())),
+            password: Some(String::from(creds.password.as_str())),
+            mfa_secret: creds.mfa_secret.clone(),
+            ..Default::default()
+        }
+    }
+
     pub fn set_username(&mut self, username: String) -> &mut Self {
         self.username = Some(username);
         self
@@ -159,17 +193,99 @@ impl FtSessionConfig {
         self
     }
 
+    /// API host and client identity, defaults to the production Firstrade API.
+    pub fn set_endpoint(&mut self, endpoint: Endpoint) -> &mut Self {
+        self.endpoint = endpoint;
+        self
+    }
+
+    /// Reuse the `ftat` saved by a previous login to skip MFA, and save the new credentials
+    /// once verified.
+    pub fn set_credential_store(&mut self, store: Arc<dyn CredentialStore>) -> &mut Self {
+        self.credential_store = Some(store);
+        self
+    }
+
     pub fn set_mfa_code(&mut self, mfa_code: String) -> &mut Self {
         self.mfa_code = Some(mfa_code);
         self
     }
 
-    pub fn set_otp_code(&mut self, otp: String) -> &mut Self {
-        self.mfa_code = Some(otp);
+    /// Base32 authenticator secret, used to generate MFA codes at login and re-login time.
+    pub fn set_mfa_secret(&mut self, mfa_secret: String) -> &mut Self {
+        self.mfa_secret = Some(MfaSecret::from(mfa_secret));
+        self
+    }
+
+    /// Account PIN, tried before requesting an OTP when the server offers the PIN fallback.
+    pub fn set_pin(&mut self, pin: String) -> &mut Self {
+        self.pin = Some(pin);
+        self
+    }
+
+    /// Email used to pick the OTP recipient automatically, must match the masked
+    /// recipient returned by the server, e.g. `l****@g****.com`.
+    pub fn set_email(&mut self, email: String) -> &mut Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Phone number used to pick the OTP recipient automatically, only the last 4 digits are compared.
+    pub fn set_phone(&mut self, phone: String) -> &mut Self {
+        self.phone = Some(phone);
         self
     }
 }
 
+/// Progress of an interactive login.
+///
+/// Every step returns the next state, which can be serialized and stored (e.g. in a web session)
+/// and later resumed with [`FtSession::submit_mfa`], [`FtSession::request_otp`],
+/// [`FtSession::submit_otp`] or [`FtSession::submit_pin`].
+///
+/// The password is never serialized, so the creds of a deserialized `Verified` state can't renew
+/// their sid: [`FtAccount::renew_sid`](crate::account::FtAccount::renew_sid) and the re-logins
+/// fail with `ErrorKind::ConfigInvalid`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum LoginState {
+    NeedsMfa {
+        t_token: String,
+    },
+    NeedsOtp {
+        options: Vec<OtpOptions>,
+        t_token: String,
+        /// The server also accepts the account PIN instead, see [`FtSession::submit_pin`].
+        #[serde(default)]
+        pin_fallback: bool,
+    },
+    /// A one-time password has been sent to the chosen recipient.
+    OtpSent {
+        t_token: String,
+        verification_sid: String,
+    },
+    NeedsPin {
+        t_token: String,
+    },
+    Verified(FtCreds),
+}
+
+impl LoginState {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, LoginState::Verified(_))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            LoginState::NeedsMfa { .. } => "needs_mfa",
+            LoginState::NeedsOtp { .. } => "needs_otp",
+            LoginState::OtpSent { .. } => "otp_sent",
+            LoginState::NeedsPin { .. } => "needs_pin",
+            LoginState::Verified(_) => "verified",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FtSessionBuilder {
     client: HttpClient,
@@ -181,6 +297,7 @@ pub struct FtSession {
     pub(crate) client: HttpClient,
     pub(crate) ft_config: FtSessionConfig,
     pub(crate) ft_creds: Option<FtCreds>,
+    login_state: Option<LoginState>,
 }
 
 impl FtSessionBuilder {
@@ -188,21 +305,28 @@ impl FtSessionBuilder {
         let client = if let Some(client) = ft_config.client.take() {
             client
         } else {
-            build_default_https_client()
+            build_default_https_client(&ft_config.endpoint)
         };
 
-        if ft_config.username.is_none() || ft_config.password.is_none() {
+        if (ft_config.username.is_none() || ft_config.password.is_none())
+            && let Some(store) = &ft_config.credential_store
+            && let Some(stored) = load_or_warn(store.as_ref())
+        {
+            // NOTE: a stored login only fills in what the config leaves out, never overrides it
+            ft_config.username = ft_config.username.take().or_else(|| stored.username.clone());
+            ft_config.password = ft_config.password.take().or_else(|| stored.password.clone());
+        }
+
+        // NOTE: creds resumed from a serialized `LoginState` come back with an empty password
+        if ft_config.username.is_none() || ft_config.password.as_deref().is_none_or(str::is_empty) {
             return Err(Error::new(
                 ErrorKind::ConfigInvalid,
                 "Username and password must be provided for login",
             ));
         }
 
-        if ft_config.ftat.is_none() && ft_config.mfa_code.is_none() {
-            return Err(Error::new(
-                ErrorKind::ConfigInvalid,
-                "Either ftat or mfa_code must be provided",
-            ));
+        if let Some(mfa_secret) = &ft_config.mfa_secret {
+            mfa_secret.validate()?;
         }
 
         Ok(Self { client, ft_config })
@@ -215,76 +339,287 @@ impl FtSession {
             client: builder.client,
             ft_config: builder.ft_config,
             ft_creds: None,
+            login_state: None,
         }
     }
 
+    /// Login non-interactively with the codes provided in [`FtSessionConfig`].
+    ///
+    /// If the server asks for something the config cannot answer (e.g. an OTP that was just sent),
+    /// this returns `ErrorKind::LoginFailed` and the login can be resumed from [`FtSession::login_state`].
     pub async fn login(&mut self) -> Result<()> {
+        let mut state = self.start_login().await?;
+        loop {
+            state = match &state {
+                LoginState::Verified(_) => return Ok(()),
+                LoginState::NeedsMfa { .. } => {
+                    let next = if let Some(mfa_code) = self.ft_config.mfa_code.clone() {
+                        self.submit_mfa(&state, mfa_code).await?
+                    } else if self.ft_config.mfa_secret.is_some() {
+                        self.submit_totp(&state).await?
+                    } else {
+                        return Err(login_credential_error(
+                            "Possible ftat is expired, need mfa to re-login",
+                        ));
+                    };
+                    if matches!(next, LoginState::NeedsMfa { .. }) {
+                        return Err(Error::new(ErrorKind::LoginFailed, "mfa code was not accepted"));
+                    }
+                    next
+                }
+                LoginState::NeedsOtp {
+                    options,
+                    pin_fallback,
+                    ..
+                } => {
+                    let recipient_id = select_otp_recipient(
+                        options,
+                        self.ft_config.email.as_deref(),
+                        self.ft_config.phone.as_deref(),
+                    );
+                    // NOTE: the PIN is tried first, it logs in without waiting for a code sent elsewhere
+                    let by_pin = match self.ft_config.pin.clone().filter(|_| *pin_fallback) {
+                        Some(pin) => Some(self.submit_pin(&state, pin).await.and_then(|next| match next {
+                            LoginState::NeedsOtp { .. } | LoginState::NeedsPin { .. } => {
+                                Err(Error::new(ErrorKind::LoginFailed, "pin was not accepted"))
+                            }
+                            next => Ok(next),
+                        })),
+                        None => None,
+                    };
+                    match (by_pin, recipient_id) {
+                        (Some(Ok(next)), _) => next,
+                        (Some(Err(err)), Some(recipient_id)) => {
+                            log::warn!("pin login failed, requesting an OTP instead: {err}");
+                            self.request_otp(&state, recipient_id).await?
+                        }
+                        (Some(Err(err)), None) => return Err(err),
+                        (None, Some(recipient_id)) => self.request_otp(&state, recipient_id).await?,
+                        (None, None) if self.ft_config.pin.is_some() => return Err(pin_disabled_error()),
+                        (None, None) => {
+                            return Err(Error::new(ErrorKind::LoginFailed, "OTP verification required"));
+                        }
+                    }
+                }
+                LoginState::OtpSent { .. } => {
+                    return Err(Error::new(
+                        ErrorKind::LoginFailed,
+                        "OTP verification required, resume with submit_otp",
+                    ));
+                }
+                LoginState::NeedsPin { .. } => {
+                    let pin = self.ft_config.pin.clone().ok_or(Error::new(
+                        ErrorKind::LoginFailed,
+                        "PIN verification required, resume with submit_pin",
+                    ))?;
+                    let next = self.submit_pin(&state, pin).await?;
+                    if matches!(next, LoginState::NeedsPin { .. }) {
+                        return Err(Error::new(ErrorKind::LoginFailed, "pin was not accepted"));
+                    }
+                    next
+                }
+            };
+        }
+    }
+
+    /// Send username and password, returning what the server needs next.
+    pub async fn start_login(&mut self) -> Result<LoginState> {
+        let stored_ftat = match (&self.ft_config.ftat, &self.ft_config.credential_store) {
+            (None, Some(store)) => load_or_warn(store.as_ref()).and_then(|creds| {
+                let username = self.ft_config.username.as_deref().unwrap_or_default();
+                // An ftat saved for another user would log in as them
+                if creds
+                    .username
+                    .as_deref()
+                    .is_some_and(|owner| owner.eq_ignore_ascii_case(username))
+                {
+                    Some(creds.ftat.clone())
+                } else {
+                    log::warn!("ignoring stored ftat, it does not belong to the configured username");
+                    None
+                }
+            }),
+            _ => None,
+        };
+
         let mut headers = HeaderMap::new();
-        if let Some(ftat) = &self.ft_config.ftat {
-            headers.insert("ftat", HeaderValue::from_str(ftat).unwrap());
+        if let Some(ftat) = self.ft_config.ftat.as_ref().or(stored_ftat.as_ref()) {
+            headers.insert(
+                "ftat",
+                HeaderValue::from_str(ftat).map_err(parse_request_header_error)?,
+            );
         }
 
         let mut body = HashMap::new();
         body.insert("username", &self.ft_config.username);
         body.insert("password", &self.ft_config.password);
 
+        let request = self.client.post(login(self.host())).headers(headers).form(&body);
+        let data = Self::send_login_request(request, "Fail to send initial login").await?;
+        self.advance(data)
+    }
+
+    pub async fn submit_mfa(&mut self, state: &LoginState, mfa_code: String) -> Result<LoginState> {
+        let LoginState::NeedsMfa { t_token } = state else {
+            return Err(login_state_error("needs_mfa", state));
+        };
+
+        log::info!("submitting MFA code for login");
+        let body = LoginMfaRequest::builder()
+            .t_token(t_token.clone())
+            .mfa_code(mfa_code)
+            .remember_for(30)
+            .build();
+        let body = serde_json::to_string(&body).unwrap();
+
+        let request = self.client.post(verify_pin(self.host())).body(body);
+        let data = Self::send_login_request(request, "Failed to send mfa login request").await?;
+        self.advance(data)
+    }
+
+    /// Submit the code of the current time window generated from the configured MFA secret.
+    ///
+    /// Only one code is sent per `t_token`, a code about to expire is replaced by the next
+    /// window's code before it's sent.
+    pub async fn submit_totp(&mut self, state: &LoginState) -> Result<LoginState> {
+        let mfa_secret = self
+            .ft_config
+            .mfa_secret
+            .clone()
+            .ok_or(login_credential_error("mfa_secret"))?;
+
+        let mfa_code = mfa_secret.current().await?;
+        self.submit_mfa(state, mfa_code).await
+    }
+
+    /// Ask the server to send a one-time password to one of the recipients listed in
+    /// [`LoginState::NeedsOtp`].
+    pub async fn request_otp(&mut self, state: &LoginState, recipient_id: i64) -> Result<LoginState> {
+        let LoginState::NeedsOtp { t_token, .. } = state else {
+            return Err(login_state_error("needs_otp", state));
+        };
+
+        let body = OtpCodeRequest::builder()
+            .recipient_id(recipient_id)
+            .t_token(t_token.clone())
+            .build();
+
         let response = self
             .client
-            .post(login())
-            .headers(headers)
+            .post(request_code(self.host()))
             .form(&body)
             .send()
             .await
-            .map_err(|e| Error::new(ErrorKind::Unexpected, "Fail to send initial login").set_source(e))?;
+            .map_err(|e| {
+                Error::new(ErrorKind::Unexpected, "Failed to send otp code request").set_source(e)
+            })?;
 
         if !response.status().is_success() {
             return Err(handle_failed_response(response).await);
         }
-
         let body = response.text().await.map_err(parse_reqwest_error)?;
-        let data: LoginResponse = serde_json::from_str(&body).map_err(parse_json_error)?;
-        Self::login_verify(self, data).await?;
-        Ok(())
+        let data: OtpCodeResponse = serde_json::from_str(&body).map_err(parse_json_error)?;
+
+        if !data.error.is_empty() {
+            return Err(Error::new(ErrorKind::LoginFailed, "request otp code failed")
+                .with_context("error", data.error)
+                .with_context("message", data.message));
+        }
+        let verification_sid = data
+            .verification_sid
+            .ok_or(login_credential_error("verificationSid"))?;
+        log::info!("otp code sent to recipient: {recipient_id}");
+
+        let next = LoginState::OtpSent {
+            t_token: t_token.clone(),
+            verification_sid,
+        };
+        self.login_state = Some(next.clone());
+        Ok(next)
     }
 
-    #[async_recursion]
-    async fn login_verify(&mut self, resp: LoginResponse) -> Result<()> {
-        match resp {
-            LoginResponse::Otp(_data) => {
-                log::error!("OTP login is not supported yet");
-                unimplemented!()
-            }
-            LoginResponse::Mfa(data) => {
-                let t_token = data.t_token;
-                let mfa_code = self.ft_config.mfa_code.clone().ok_or(login_credential_error(
-                    "Possible ftat is expired, need mfa to re-login",
-                ))?;
-                log::info!("using MFA code: {} for login", mfa_code);
-
-                let body = LoginMfaRequest::builder()
-                    .t_token(t_token)
-                    .mfa_code(mfa_code)
-                    .remember_for(30)
-                    .build();
-                let body = serde_json::to_string(&body).unwrap();
-
-                let response = self
-                    .client
-                    .post(verify_pin())
-                    .body(body)
-                    .send()
-                    .await
-                    .map_err(|e| {
-                        Error::new(ErrorKind::Unexpected, "Failed to send mfa login request").set_source(e)
-                    })?;
-
-                if !response.status().is_success() {
-                    return Err(handle_failed_response(response).await);
+    pub async fn submit_otp(&mut self, state: &LoginState, otp_code: String) -> Result<LoginState> {
+        let LoginState::OtpSent {
+            t_token,
+            verification_sid,
+        } = state
+        else {
+            return Err(login_state_error("otp_sent", state));
+        };
+
+        let body = LoginOtpRequest::builder()
+            .otp_code(otp_code)
+            .verification_sid(verification_sid.clone())
+            .remember_for(30)
+            .t_token(t_token.clone())
+            .build();
+
+        let request = self.client.post(verify_pin(self.host())).form(&body);
+        let data = Self::send_login_request(request, "Failed to send otp login request").await?;
+        self.advance(data)
+    }
+
+    /// Verify with the account PIN, either when the server asks for it or as the fallback
+    /// offered alongside OTP.
+    pub async fn submit_pin(&mut self, state: &LoginState, pin: String) -> Result<LoginState> {
+        let t_token = match state {
+            LoginState::NeedsPin { t_token } => t_token,
+            LoginState::NeedsOtp {
+                t_token,
+                pin_fallback: true,
+                ..
+            } => t_token,
+            LoginState::NeedsOtp { .. } => return Err(pin_disabled_error()),
+            _ => return Err(login_state_error("needs_pin", state)),
+        };
+
+        let body = LoginPinRequest::builder()
+            .pin(pin)
+            .remember_for(30)
+            .t_token(t_token.clone())
+            .build();
+
+        let request = self.client.post(verify_pin(self.host())).form(&body);
+        let data = Self::send_login_request(request, "Failed to send pin login request").await?;
+        self.advance(data)
+    }
+
+    /// Returns the last state reached by the login steps.
+    pub fn login_state(&self) -> Option<&LoginState> {
+        self.login_state.as_ref()
+    }
+
+    async fn send_login_request(request: RequestBuilder, message: &'static str) -> Result<LoginResponse> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| Error::new(ErrorKind::Unexpected, message).set_source(e))?;
+
+        if !response.status().is_success() {
+            return Err(handle_failed_response(response).await);
+        }
+        parse_success_response(response).await
+    }
+
+    fn advance(&mut self, resp: LoginResponse) -> Result<LoginState> {
+        let state = match resp {
+            LoginResponse::Otp(data) => {
+                let pin_fallback = data.fallback.strategy == "pin" && !data.fallback.pin_disabled;
+                match (data.otp.is_empty(), pin_fallback) {
+                    (true, true) => LoginState::NeedsPin {
+                        t_token: data.t_token,
+                    },
+                    (true, false) => return Err(pin_disabled_error()),
+                    (false, _) => LoginState::NeedsOtp {
+                        options: data.otp,
+                        t_token: data.t_token,
+                        pin_fallback,
+                    },
                 }
-                let body = response.text().await.map_err(parse_reqwest_error)?;
-                let data: LoginResponse = serde_json::from_str(&body).map_err(parse_json_error)?;
-                Ok(Self::login_verify(self, data).await?)
             }
+            LoginResponse::Mfa(data) => LoginState::NeedsMfa {
+                t_token: data.t_token,
+            },
             LoginResponse::Verify(data) => {
                 log::info!("login verified successfully");
                 let username = self.ft_config.username.clone().unwrap();
@@ -294,11 +629,17 @@ impl FtSession {
                     password: FirstTradePassword(password),
                     ftat: FirstTradeAccessToken(data.ftat),
                     sid: SessionId(data.sid),
+                    mfa_secret: self.ft_config.mfa_secret.clone(),
                 };
-                self.set_ft_creds(ft_cred);
-                Ok(())
+                self.set_ft_creds(ft_cred.clone());
+                if let Err(err) = self.save_creds() {
+                    log::warn!("failed to save credentials: {err}");
+                }
+                LoginState::Verified(ft_cred)
             }
-        }
+        };
+        self.login_state = Some(state.clone());
+        Ok(state)
     }
 
     pub fn set_ft_creds(&mut self, creds: FtCreds) {
@@ -309,6 +650,27 @@ impl FtSession {
         self.ft_creds.clone()
     }
 
+    /// Save the current credentials to the configured [`CredentialStore`], if any.
+    pub fn save_creds(&self) -> Result<()> {
+        match (&self.ft_config.credential_store, &self.ft_creds) {
+            (Some(store), Some(creds)) => store.save(&StoredCreds::from(creds)),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn credential_store(&self) -> Option<Arc<dyn CredentialStore>> {
+        self.ft_config.credential_store.clone()
+    }
+
+    pub(crate) fn endpoint(&self) -> &Endpoint {
+        &self.ft_config.endpoint
+    }
+
+    #[inline]
+    fn host(&self) -> &str {
+        self.ft_config.endpoint.base_url()
+    }
+
     pub async fn get_account_list(&self) -> Result<AccountList> {
         if let Some(ft_creds) = &self.ft_creds {
             let mut headers = HeaderMap::new();
@@ -322,12 +684,13 @@ impl FtSession {
             );
             headers.insert(
                 "access-token",
-                HeaderValue::from_str(ACCESS_TOKEN).map_err(parse_request_header_error)?,
+                HeaderValue::from_str(self.ft_config.endpoint.access_token())
+                    .map_err(parse_request_header_error)?,
             );
 
             let response = self
                 .client
-                .get(account_list())
+                .get(account_list(self.host()))
                 .headers(headers)
                 .send()
                 .await
@@ -338,12 +701,219 @@ impl FtSession {
             if !response.status().is_success() {
                 return Err(handle_failed_response(response).await);
             }
-            let body = response.text().await.map_err(parse_reqwest_error)?;
-            let data: AccountList = serde_json::from_str(&body).map_err(parse_json_error)?;
-
-            Ok(data)
+            parse_success_response(response).await
         } else {
             Err(login_credential_error("ft_creds"))
         }
     }
 }
+
+#[inline]
+fn pin_disabled_error() -> Error {
+    Error::new(
+        ErrorKind::LoginFailed,
+        "PIN fallback is disabled for this account, verify with OTP instead",
+    )
+}
+
+#[inline]
+fn login_state_error(expected: &'static str, actual: &LoginState) -> Error {
+    Error::new(ErrorKind::ConditionNotMatch, "unexpected login state")
+        .with_context("expected", expected)
+        .with_context("actual", actual.name())
+}
+
+/// Pick the OTP recipient matching the configured email or phone.
+fn select_otp_recipient(options: &[OtpOptions], email: Option<&str>, phone: Option<&str>) -> Option<i64> {
+    options
+        .iter()
+        .find(|o| match o.channel.as_str() {
+            "email" => email.is_some_and(|email| email_matches_mask(email, &o.recipient_mask)),
+            "sms" => phone.is_some_and(|phone| {
+                let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+                digits.len() >= 4 && o.recipient_mask.ends_with(&digits[digits.len() - 4..])
+            }),
+            _ => false,
+        })
+        .map(|o| o.recipient_id)
+}
+
+/// Whether `email` fits a mask like `l****@g****.com`, each run of `*` standing for any characters.
+fn email_matches_mask(email: &str, mask: &str) -> bool {
+    let email = email.trim().to_ascii_lowercase();
+    let mask = mask.to_ascii_lowercase();
+    if !email.contains('@') || !mask.contains('*') {
+        return email == mask;
+    }
+
+    let parts: Vec<&str> = mask.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if email.len() < first.len() + last.len() || !email.starts_with(first) || !email.ends_with(last) {
+        return false;
+    }
+    let mut rest = &email[first.len()..email.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn otp_options() -> Vec<OtpOptions> {
+        vec![
+            OtpOptions {
+                recipient_id: 354687,
+                channel: "sms".to_string(),
+                recipient_mask: "+1******0811".to_string(),
+            },
+            OtpOptions {
+                recipient_id: 120129,
+                channel: "email".to_string(),
+                recipient_mask: "l****@g****.com".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_select_otp_recipient() {
+        let options = otp_options();
+        assert_eq!(
+            select_otp_recipient(&options, None, Some("+1 (555) 123-0811")),
+            Some(354687)
+        );
+        assert_eq!(
+            select_otp_recipient(&options, Some("l****@g****.com"), None),
+            Some(120129)
+        );
+        assert_eq!(
+            select_otp_recipient(&options, Some("Lucas.Smith@Gmail.com"), None),
+            Some(120129)
+        );
+        assert_eq!(select_otp_recipient(&options, Some("lucas@gmail.co"), None), None);
+        assert_eq!(
+            select_otp_recipient(&options, Some("lucas@yahoo.com"), None),
+            None
+        );
+        assert_eq!(select_otp_recipient(&options, Some("mike@gmail.com"), None), None);
+        assert_eq!(select_otp_recipient(&options, None, Some("0812")), None);
+        assert_eq!(select_otp_recipient(&options, None, Some("11")), None);
+        assert_eq!(select_otp_recipient(&options, None, None), None);
+    }
+
+    #[test]
+    fn test_login_state_round_trip() {
+        let state = LoginState::NeedsOtp {
+            options: otp_options(),
+            t_token: "1f4b31f064cb12366e397192db86fcbf".to_string(),
+            pin_fallback: true,
+        };
+        let raw = serde_json::to_string(&state).unwrap();
+        let state: LoginState = serde_json::from_str(&raw).unwrap();
+        match state {
+            LoginState::NeedsOtp {
+                options,
+                t_token,
+                pin_fallback,
+            } => {
+                assert_eq!(options.len(), 2);
+                assert!(pin_fallback);
+                assert_eq!(t_token, "1f4b31f064cb12366e397192db86fcbf");
+            }
+            _ => panic!("Expected LoginState::NeedsOtp"),
+        }
+
+        let state = LoginState::Verified(FtCreds {
+            username: FirstTradeUsername::from("user".to_string()),
+            password: FirstTradePassword::from("hunter2".to_string()),
+            ftat: FirstTradeAccessToken::from("ftat".to_string()),
+            sid: SessionId::from("sid".to_string()),
+            mfa_secret: Some(MfaSecret::from("GEZDGNBVGY3TQOJQ".to_string())),
+        });
+        let raw = serde_json::to_value(&state).unwrap();
+        assert_eq!(raw["state"], "verified");
+        assert!(raw.get("password").is_none());
+        assert!(!raw.to_string().contains("hunter2"));
+        let state: LoginState = serde_json::from_value(raw).unwrap();
+        match state {
+            LoginState::Verified(creds) => {
+                assert_eq!(creds.get_ftat(), "ftat");
+                assert_eq!(creds.get_sid(), "sid");
+                assert!(creds.mfa_secret.is_none());
+            }
+            _ => panic!("Expected LoginState::Verified"),
+        }
+    }
+
+    fn test_session() -> FtSession {
+        let mut ft_config = FtSessionConfig::default();
+        ft_config
+            .set_username("user".to_string())
+            .set_password("pass".to_string());
+        FtSession::from_builder(FtSessionBuilder::new(ft_config).unwrap())
+    }
+
+    fn otp_response(otp: serde_json::Value, pin_disabled: bool) -> LoginResponse {
+        serde_json::from_value(serde_json::json!({
+            "statusCode": 200,
+            "error": "",
+            "message": "Normal",
+            "t_token": "1f4b31f064cb12366e397192db86fcbf",
+            "mfa": false,
+            "otp": otp,
+            "fallback": {
+                "strategy": "pin",
+                "pinDisabled": pin_disabled
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_advance_pin_fallback() {
+        let mut session = test_session();
+
+        let state = session
+            .advance(otp_response(serde_json::json!([]), false))
+            .unwrap();
+        assert!(matches!(state, LoginState::NeedsPin { .. }));
+
+        let err = session
+            .advance(otp_response(serde_json::json!([]), true))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::LoginFailed);
+
+        let options = serde_json::to_value(otp_options()).unwrap();
+        let state = session.advance(otp_response(options.clone(), true)).unwrap();
+        assert!(matches!(
+            state,
+            LoginState::NeedsOtp {
+                pin_fallback: false,
+                ..
+            }
+        ));
+        let state = session.advance(otp_response(options, false)).unwrap();
+        assert!(matches!(
+            state,
+            LoginState::NeedsOtp {
+                pin_fallback: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_login_state_error() {
+        let state = LoginState::NeedsPin {
+            t_token: "073e8a2ae7331c32e8b0c12004248e00".to_string(),
+        };
+        let err = login_state_error("needs_mfa", &state);
+        assert_eq!(err.kind(), ErrorKind::ConditionNotMatch);
+        assert!(err.to_string().contains("actual: needs_pin"));
+    }
+}
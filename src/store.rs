@@ -0,0 +1,311 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::session::FtCreds;
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use data_encoding::BASE64;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::fs;
+use std::io::ErrorKind as IoErrorKind;
+use std::path::PathBuf;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+const FILE_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+
+/// Credentials persisted between restarts, a stored `ftat` lets the next login skip MFA.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct StoredCreds {
+    pub ftat: String,
+    pub sid: String,
+    /// Owner of `ftat`, a stored `ftat` is only sent when logging in as the same user.
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Debug for StoredCreds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredCreds")
+            .field("ftat", &"***")
+            .field("sid", &"***")
+            .field("username", &self.username.as_ref().map(|_| "***"))
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl From<&FtCreds> for StoredCreds {
+    fn from(creds: &FtCreds) -> Self {
+        Self {
+            ftat: creds.get_ftat(),
+            sid: creds.get_sid(),
+            username: Some(String::from(creds.username.as_str())),
+            password: Some(String::from(creds.password.as_str())),
+        }
+    }
+}
+
+/// Where [`FtSession`](crate::session::FtSession) and [`FtAccount`](crate::account::FtAccount)
+/// keep credentials between restarts.
+pub trait CredentialStore: Send + Sync {
+    /// Returns `None` when nothing has been saved yet.
+    fn load(&self) -> Result<Option<StoredCreds>>;
+
+    fn save(&self, creds: &StoredCreds) -> Result<()>;
+
+    fn clear(&self) -> Result<()>;
+}
+
+/// Loads `store`, treating one that can't be read (e.g. a corrupt file or a wrong passphrase)
+/// as empty so the caller falls back to a normal login.
+pub(crate) fn load_or_warn(store: &dyn CredentialStore) -> Option<StoredCreds> {
+    store.load().unwrap_or_else(|err| {
+        log::warn!("ignoring unreadable credential store: {err}");
+        None
+    })
+}
+
+/// File backed [`CredentialStore`], encrypted with ChaCha20-Poly1305 under a key derived
+/// from a passphrase with Argon2id.
+pub struct FileCredentialStore {
+    path: PathBuf,
+    passphrase: Zeroizing<String>,
+    include_login: bool,
+}
+
+impl Debug for FileCredentialStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileCredentialStore")
+            .field("path", &self.path)
+            .field("passphrase", &"***")
+            .field("include_login", &self.include_login)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl FileCredentialStore {
+    pub fn new(path: impl Into<PathBuf>, passphrase: String) -> Self {
+        Self {
+            path: path.into(),
+            passphrase: Zeroizing::new(passphrase),
+            include_login: false,
+        }
+    }
+
+    /// Also persist the password, by default only `ftat`, `sid` and the username they belong to are saved.
+    ///
+    /// With both stored, [`FtSessionBuilder::new`](crate::session::FtSessionBuilder::new) accepts a
+    /// config without username and password.
+    pub fn with_login(mut self, include_login: bool) -> Self {
+        self.include_login = include_login;
+        self
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(self.passphrase.as_bytes(), salt, key.as_mut())
+            .map_err(|e| Error::new(ErrorKind::ConfigInvalid, format!("deriving store key: {e}")))?;
+        Ok(key)
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedFile> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = self.derive_key(&salt)?;
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| Error::new(ErrorKind::Unexpected, "encrypting credentials"))?;
+
+        Ok(EncryptedFile {
+            version: FILE_VERSION,
+            salt: BASE64.encode(&salt),
+            nonce: BASE64.encode(&nonce),
+            ciphertext: BASE64.encode(&ciphertext),
+        })
+    }
+
+    fn decrypt(&self, file: &EncryptedFile) -> Result<Zeroizing<Vec<u8>>> {
+        if file.version != FILE_VERSION {
+            return Err(
+                Error::new(ErrorKind::Unsupported, "unknown credential store version")
+                    .with_context("version", file.version),
+            );
+        }
+        let decode = |field: &str| {
+            BASE64
+                .decode(field.as_bytes())
+                .map_err(|e| Error::new(ErrorKind::ConfigInvalid, "corrupted credential store").set_source(e))
+        };
+        let salt = decode(&file.salt)?;
+        let nonce = decode(&file.nonce)?;
+        let ciphertext = decode(&file.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(Error::new(ErrorKind::ConfigInvalid, "corrupted credential store"));
+        }
+
+        let key = self.derive_key(&salt)?;
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| {
+                Error::new(
+                    ErrorKind::ConfigInvalid,
+                    "failed to decrypt credential store, wrong passphrase?",
+                )
+            })?;
+        Ok(Zeroizing::new(plaintext))
+    }
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn load(&self) -> Result<Option<StoredCreds>> {
+        let raw = match fs::read_to_string(&self.path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == IoErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(Error::new(ErrorKind::Unexpected, "reading credential store")
+                    .with_context("path", self.path.display())
+                    .set_source(e));
+            }
+        };
+        let file: EncryptedFile = serde_json::from_str(&raw).map_err(|e| {
+            Error::new(ErrorKind::ConfigInvalid, "corrupted credential store")
+                .with_context("path", self.path.display())
+                .set_source(e)
+        })?;
+
+        let plaintext = self.decrypt(&file)?;
+        let creds = serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::new(ErrorKind::ConfigInvalid, "corrupted credential store").set_source(e))?;
+        Ok(Some(creds))
+    }
+
+    fn save(&self, creds: &StoredCreds) -> Result<()> {
+        let mut creds = creds.clone();
+        if !self.include_login {
+            creds.password = None;
+        }
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&creds)
+                .map_err(|e| Error::new(ErrorKind::Unexpected, "serializing credentials").set_source(e))?,
+        );
+        let file = self.encrypt(&plaintext)?;
+        let raw = serde_json::to_string(&file)
+            .map_err(|e| Error::new(ErrorKind::Unexpected, "serializing credential store").set_source(e))?;
+
+        // NOTE: write to a temporary file first, so a crash never leaves a truncated store behind
+        let tmp = self.path.with_extension("tmp");
+        write_private(&tmp, raw.as_bytes()).map_err(|e| {
+            Error::new(ErrorKind::Unexpected, "writing credential store")
+                .with_context("path", tmp.display())
+                .set_source(e)
+        })?;
+        fs::rename(&tmp, &self.path).map_err(|e| {
+            Error::new(ErrorKind::Unexpected, "writing credential store")
+                .with_context("path", self.path.display())
+                .set_source(e)
+        })
+    }
+
+    fn clear(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == IoErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::new(ErrorKind::Unexpected, "removing credential store")
+                .with_context("path", self.path.display())
+                .set_source(e)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn write_private(path: &std::path::Path, data: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &std::path::Path, data: &[u8]) -> std::io::Result<()> {
+    fs::write(path, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("firstrade-{}-{name}.json", std::process::id()))
+    }
+
+    fn stored_creds() -> StoredCreds {
+        StoredCreds {
+            ftat: "3B3812FC07A431A911A193C5CA1D8A63B184D6E88FA3DAC4CE4DA7D703DBC9C0".to_string(),
+            sid: "87DEF0CF1D54F7F208CD486F9B025CF101E8D0F4DDEC54E24F414C1F1187E302".to_string(),
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_file_store_round_trip() {
+        let path = temp_path("round-trip");
+        let store = FileCredentialStore::new(&path, "passphrase".to_string());
+        assert!(store.load().unwrap().is_none());
+
+        store.save(&stored_creds()).unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("3B3812FC07A431A911A193C5CA1D8A63"));
+
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.ftat, stored_creds().ftat);
+        assert_eq!(loaded.sid, stored_creds().sid);
+        assert_eq!(loaded.username.as_deref(), Some("user"));
+        assert!(loaded.password.is_none());
+
+        let store = store.with_login(true);
+        store.save(&stored_creds()).unwrap();
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded.username.as_deref(), Some("user"));
+        assert_eq!(loaded.password.as_deref(), Some("pass"));
+
+        store.clear().unwrap();
+        assert!(store.load().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_file_store_wrong_passphrase() {
+        let path = temp_path("wrong-passphrase");
+        FileCredentialStore::new(&path, "passphrase".to_string())
+            .save(&stored_creds())
+            .unwrap();
+
+        let err = FileCredentialStore::new(&path, "other".to_string())
+            .load()
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,407 @@
+use crate::account::FtAccount;
+use crate::error::{Error, ErrorKind, Result};
+use crate::models::account::{HistoryFilter, HistoryItem};
+use crate::models::option::OccSymbol;
+use crate::models::security::{SecurityType, Symbol};
+use crate::models::watchlist::{ItemQuote, WatchListResult};
+use futures::Stream;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const DEFAULT_HISTORY_PER_PAGE: u32 = 200;
+
+/// Shortest interval a [`QuoteStream`] polls at.
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name of the watchlist a [`QuoteStream`] keeps its symbols in, deleted with the stream.
+pub const QUOTE_STREAM_WATCHLIST: &str = "firstrade-rs quote stream";
+
+#[derive(Debug)]
+pub enum QuoteUpdate {
+    /// First quote of a symbol, or a quote that changed since the last one.
+    Quote { symbol: Symbol, quote: Box<ItemQuote> },
+    /// The symbol could not be quoted, only sent again after it recovers and fails anew.
+    ///
+    /// A symbol the watchlist rejects (`ErrorKind::NotFound`) isn't retried until it's removed
+    /// and added again.
+    Failed { symbol: Symbol, error: Error },
+}
+
+impl QuoteUpdate {
+    pub fn symbol(&self) -> &Symbol {
+        match self {
+            QuoteUpdate::Quote { symbol, .. } | QuoteUpdate::Failed { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// Adds and removes symbols of a running [`QuoteStream`], changes apply from the next poll.
+#[derive(Debug, Clone, Default)]
+pub struct QuoteStreamHandle {
+    symbols: Arc<Mutex<BTreeSet<Symbol>>>,
+}
+
+impl QuoteStreamHandle {
+    /// Returns `false` if the symbol was already subscribed.
+    pub fn add(&self, symbol: Symbol) -> bool {
+        self.symbols.lock().unwrap().insert(symbol)
+    }
+
+    /// Returns `false` if the symbol wasn't subscribed.
+    pub fn remove(&self, symbol: &Symbol) -> bool {
+        self.symbols.lock().unwrap().remove(symbol)
+    }
+
+    pub fn symbols(&self) -> Vec<Symbol> {
+        self.symbols.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+// NOTE: Firstrade's streaming quotes (`streaming_quote` in `UserInfo`) are not reverse-engineered,
+// so this keeps the symbols in a watchlist of its own and quotes all of them with one
+// `FtAccount::get_watchlist_quote` request per poll, only yielding what changed
+/// A [`Stream`] of quote updates for a set of symbols, polled every interval.
+///
+/// The stream creates the [`QUOTE_STREAM_WATCHLIST`] watchlist on its first poll and deletes it
+/// when dropped. Subscribing or unsubscribing a symbol costs one extra request on the next poll.
+pub struct QuoteStream {
+    handle: QuoteStreamHandle,
+    inner: BoxStream<'static, QuoteUpdate>,
+}
+
+struct PollState {
+    account: FtAccount,
+    handle: QuoteStreamHandle,
+    interval: Duration,
+    next_poll: Instant,
+    list_id: Option<u32>,
+    // Watchlist item id of every symbol in the list
+    items: HashMap<Symbol, u32>,
+    rejected: HashSet<Symbol>,
+    // `None` when the last poll of the symbol failed
+    last: HashMap<Symbol, Option<ItemQuote>>,
+    pending: VecDeque<QuoteUpdate>,
+}
+
+impl PollState {
+    async fn poll_once(&mut self) {
+        tokio::time::sleep_until(self.next_poll).await;
+        self.next_poll = Instant::now() + self.interval.max(MIN_POLL_INTERVAL);
+        let symbols = self.handle.symbols();
+        self.last.retain(|symbol, _| symbols.contains(symbol));
+        self.rejected.retain(|symbol| symbols.contains(symbol));
+
+        let list_id = match self.list_id {
+            Some(list_id) => list_id,
+            None => match self.create_watchlist().await {
+                Ok(list_id) => *self.list_id.insert(list_id),
+                Err(error) => {
+                    let symbols: Vec<_> = symbols
+                        .into_iter()
+                        .filter(|s| !self.rejected.contains(s))
+                        .collect();
+                    return self.fail_all(&symbols, &error);
+                }
+            },
+        };
+        self.sync_watchlist(list_id, &symbols).await;
+        if self.items.is_empty() {
+            return;
+        }
+
+        let listed: Vec<_> = self.items.keys().cloned().collect();
+        match self.account.get_watchlist_quote(list_id).await {
+            Ok(resp) if resp.error.is_empty() => {
+                for quote in resp.result.map(|list| list.list_items).unwrap_or_default() {
+                    if let Ok(symbol) = Symbol::new(&quote.symbol)
+                        && self.items.contains_key(&symbol)
+                    {
+                        self.update(symbol, Ok(quote));
+                    }
+                }
+            }
+            Ok(resp) => {
+                let error = Error::new(ErrorKind::Unexpected, "watchlist quote failed")
+                    .with_context("watchlist", list_id)
+                    .with_context("error", resp.error)
+                    .with_context("message", resp.message);
+                self.fail_all(&listed, &error);
+            }
+            Err(error) => self.fail_all(&listed, &error),
+        }
+    }
+
+    async fn create_watchlist(&self) -> Result<u32> {
+        let resp = self
+            .account
+            .add_new_watchlist(QUOTE_STREAM_WATCHLIST.to_string())
+            .await?;
+        match resp.result {
+            Some(WatchListResult::AddNewWatchList(list)) if resp.error.is_empty() => Ok(list.list_id as u32),
+            _ => Err(Error::new(
+                ErrorKind::Unexpected,
+                "failed to create the quote stream watchlist",
+            )
+            .with_context("error", resp.error)
+            .with_context("message", resp.message)),
+        }
+    }
+
+    /// Adds new symbols to the watchlist and removes unsubscribed ones.
+    async fn sync_watchlist(&mut self, list_id: u32, symbols: &[Symbol]) {
+        let removed: Vec<_> = self
+            .items
+            .keys()
+            .filter(|symbol| !symbols.contains(symbol))
+            .cloned()
+            .collect();
+        for symbol in removed {
+            let Some(item_id) = self.items.remove(&symbol) else {
+                continue;
+            };
+            match self.account.watchlist_remove_symbol(item_id).await {
+                Ok(resp) if resp.error.is_empty() => {}
+                Ok(resp) => log::warn!(
+                    "failed to remove {symbol} from the quote stream watchlist: {}",
+                    resp.message
+                ),
+                Err(err) => log::warn!("failed to remove {symbol} from the quote stream watchlist: {err}"),
+            }
+        }
+
+        for symbol in symbols {
+            if self.items.contains_key(symbol) || self.rejected.contains(symbol) {
+                continue;
+            }
+            match self.add_symbol(list_id, symbol).await {
+                Ok(item_id) => {
+                    self.items.insert(symbol.clone(), item_id);
+                }
+                Err(error) => {
+                    if error.kind() == ErrorKind::NotFound {
+                        self.rejected.insert(symbol.clone());
+                    }
+                    self.update(symbol.clone(), Err(error));
+                }
+            }
+        }
+    }
+
+    /// Watchlist item id of the added symbol, `ErrorKind::NotFound` if the server rejects it.
+    async fn add_symbol(&self, list_id: u32, symbol: &Symbol) -> Result<u32> {
+        let sec_type = match symbol.as_str().parse::<OccSymbol>() {
+            Ok(_) => SecurityType::Option,
+            Err(_) => SecurityType::Equity,
+        };
+        let resp = self
+            .account
+            .watchlist_add_symbol(list_id, symbol.to_string(), sec_type)
+            .await?;
+        match resp.result {
+            Some(WatchListResult::AddNewSymbol(item)) if resp.error.is_empty() => {
+                Ok(item.watchlist_id as u32)
+            }
+            _ => Err(Error::new(ErrorKind::NotFound, "quote not found")
+                .with_context("symbol", symbol.as_str())
+                .with_context("error", resp.error)
+                .with_context("message", resp.message)),
+        }
+    }
+
+    fn fail_all(&mut self, symbols: &[Symbol], error: &Error) {
+        for symbol in symbols {
+            let error = Error::new(error.kind(), "quote stream poll failed")
+                .with_context("symbol", symbol.as_str())
+                .with_context("source", error);
+            self.update(symbol.clone(), Err(error));
+        }
+    }
+
+    /// Queues the result unless it repeats the last one of the symbol.
+    fn update(&mut self, symbol: Symbol, result: Result<ItemQuote>) {
+        match result {
+            Ok(quote) => {
+                if matches!(self.last.get(&symbol), Some(Some(last)) if *last == quote) {
+                    return;
+                }
+                self.last.insert(symbol.clone(), Some(quote.clone()));
+                self.pending.push_back(QuoteUpdate::Quote {
+                    symbol,
+                    quote: Box::new(quote),
+                });
+            }
+            Err(error) => {
+                if matches!(self.last.get(&symbol), Some(None)) {
+                    return;
+                }
+                self.last.insert(symbol.clone(), None);
+                self.pending.push_back(QuoteUpdate::Failed { symbol, error });
+            }
+        }
+    }
+}
+
+impl Drop for PollState {
+    fn drop(&mut self) {
+        // Best effort, the watchlist is left behind if no runtime is around to delete it
+        if let Some(list_id) = self.list_id
+            && let Ok(runtime) = tokio::runtime::Handle::try_current()
+        {
+            let account = self.account.clone();
+            runtime.spawn(async move {
+                if let Err(err) = account.delete_watchlist(list_id).await {
+                    log::warn!("failed to delete the quote stream watchlist {list_id}: {err}");
+                }
+            });
+        }
+    }
+}
+
+impl QuoteStream {
+    pub(crate) fn new(
+        account: FtAccount,
+        symbols: impl IntoIterator<Item = Symbol>,
+        interval: Duration,
+    ) -> Self {
+        let handle = QuoteStreamHandle::default();
+        for symbol in symbols {
+            handle.add(symbol);
+        }
+
+        let state = PollState {
+            account,
+            handle: handle.clone(),
+            interval,
+            next_poll: Instant::now(),
+            list_id: None,
+            items: HashMap::new(),
+            rejected: HashSet::new(),
+            last: HashMap::new(),
+            pending: VecDeque::new(),
+        };
+
+        let inner = stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(update) = state.pending.pop_front() {
+                    return Some((update, state));
+                }
+                state.poll_once().await;
+            }
+        })
+        .boxed();
+
+        Self { handle, inner }
+    }
+
+    pub fn handle(&self) -> QuoteStreamHandle {
+        self.handle.clone()
+    }
+
+    pub fn add(&self, symbol: Symbol) -> bool {
+        self.handle.add(symbol)
+    }
+
+    pub fn remove(&self, symbol: &Symbol) -> bool {
+        self.handle.remove(symbol)
+    }
+
+    pub fn symbols(&self) -> Vec<Symbol> {
+        self.handle.symbols()
+    }
+}
+
+impl Stream for QuoteStream {
+    type Item = QuoteUpdate;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+// ==================== History ====================
+/// Items of every history page, see [`FtAccount::history_stream`].
+pub type HistoryStream = BoxStream<'static, Result<HistoryItem>>;
+
+struct HistoryPages {
+    account: FtAccount,
+    range: String,
+    filter: HistoryFilter,
+    per_page: u32,
+    page: u32,
+    fetched: u32,
+    done: bool,
+    pending: VecDeque<HistoryItem>,
+}
+
+impl HistoryPages {
+    async fn fetch_page(&mut self) -> Result<()> {
+        let resp = self
+            .account
+            .get_account_history(&self.range, self.page, self.per_page)
+            .await?;
+        if !resp.error.is_empty() {
+            return Err(Error::new(ErrorKind::Unexpected, "history request failed")
+                .with_context("page", self.page.to_string())
+                .with_context("error", resp.error)
+                .with_context("message", resp.message));
+        }
+
+        let items = resp.items.unwrap_or_default();
+        let count = items.len() as u32;
+        self.fetched += count;
+        // A short page is the last one even if `total` says otherwise
+        self.done = count == 0 || count < self.per_page || self.fetched >= resp.total;
+        self.page += 1;
+        self.pending.extend(items);
+        Ok(())
+    }
+
+    fn accept(&self, item: &HistoryItem) -> Result<bool> {
+        if !self.filter.is_dated() {
+            return Ok(true);
+        }
+        let date = item.parsed_report_date().ok_or_else(|| {
+            Error::new(ErrorKind::Unexpected, "invalid history report date")
+                .with_context("report_date", &item.report_date)
+        })?;
+        Ok(self.filter.contains(date))
+    }
+}
+
+pub(crate) fn history_stream(account: FtAccount, range: &str, filter: HistoryFilter) -> HistoryStream {
+    let pages = HistoryPages {
+        account,
+        range: range.to_string(),
+        per_page: filter.per_page.unwrap_or(DEFAULT_HISTORY_PER_PAGE).max(1),
+        filter,
+        page: 1,
+        fetched: 0,
+        done: false,
+        pending: VecDeque::new(),
+    };
+
+    stream::unfold(pages, |mut pages| async move {
+        loop {
+            if let Some(item) = pages.pending.pop_front() {
+                match pages.accept(&item) {
+                    Ok(true) => return Some((Ok(item), pages)),
+                    Ok(false) => continue,
+                    Err(err) => return Some((Err(err), pages)),
+                }
+            }
+            if pages.done {
+                return None;
+            }
+            if let Err(err) = pages.fetch_page().await {
+                pages.done = true;
+                return Some((Err(err), pages));
+            }
+        }
+    })
+    .boxed()
+}
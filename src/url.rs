@@ -1,114 +1,202 @@
-pub(crate) const FT_HOST: &str = "https://api3x.firstrade.com";
-pub(crate) static ACCESS_TOKEN: &str = "833w3XuIFycv18ybi";
-pub(crate) static USER_AGENT: &str = "okhttp/4.9.2";
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use std::fmt::Display;
 
+// NOTE: every builder takes the base url from `Endpoint::base_url`, e.g. "https://api3x.firstrade.com"
+
+// Everything but the unreserved characters of RFC 3986
+const QUERY_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Percent-encode a caller supplied query value, e.g. a symbol.
 #[inline(always)]
-pub fn login() -> String {
-    format!("{FT_HOST}/sess/login")
+fn encode(value: &str) -> impl Display + '_ {
+    utf8_percent_encode(value, QUERY_VALUE)
 }
 
 #[inline(always)]
-pub fn verify_pin() -> String {
-    format!("{FT_HOST}/sess/verify_pin")
+pub fn login(host: &str) -> String {
+    format!("{host}/sess/login")
 }
 
-// #[inline(always)]
-// pub fn profile() -> String {
-//     format!("{FT_HOST}/account/profile")
-// }
+#[inline(always)]
+pub fn verify_pin(host: &str) -> String {
+    format!("{host}/sess/verify_pin")
+}
 
 // #[inline(always)]
-// pub fn request_code() -> String {
-//     format!("{FT_HOST}/sess/request_code")
+// pub fn profile(host: &str) -> String {
+//     format!("{host}/account/profile")
 // }
 
+#[inline(always)]
+pub fn request_code(host: &str) -> String {
+    format!("{host}/sess/request_code")
+}
+
 // #[inline(always)]
-// pub fn jwt_auth() -> String {
-//     format!("{FT_HOST}/private/tc/authenticate")
+// pub fn jwt_auth(host: &str) -> String {
+//     format!("{host}/private/tc/authenticate")
 // }
 
 #[inline(always)]
-pub fn market_time() -> String {
-    format!("{FT_HOST}/public/market_time")
+pub fn market_time(host: &str) -> String {
+    format!("{host}/public/market_time")
 }
 
 #[inline(always)]
-pub fn account_list() -> String {
-    format!("{FT_HOST}/private/acct_list")
+pub fn account_list(host: &str) -> String {
+    format!("{host}/private/acct_list")
 }
 
 #[inline(always)]
-pub fn user_info(account: &str) -> String {
-    format!("{FT_HOST}/private/userinfo?account={account}")
+pub fn user_info(host: &str, account: &str) -> String {
+    format!("{host}/private/userinfo?account={account}")
 }
 
 #[inline(always)]
-pub fn account_balances(account: &str) -> String {
-    format!("{FT_HOST}/private/balances?account={account}")
+pub fn account_balances(host: &str, account: &str) -> String {
+    format!("{host}/private/balances?account={account}")
 }
 
 #[inline(always)]
-pub fn account_history(account: &str, range: &str, page: u32, per_page: u32) -> String {
-    format!(
-        "{FT_HOST}/private/account_history?range={range}&page={page}&account={account}&per_page={per_page}"
-    )
+pub fn account_history(host: &str, account: &str, range: &str, page: u32, per_page: u32) -> String {
+    format!("{host}/private/account_history?range={range}&page={page}&account={account}&per_page={per_page}")
 }
 
 #[inline(always)]
-pub fn account_positions(account: &str) -> String {
-    format!("{FT_HOST}/private/positions?account={account}&per_page=200")
+pub fn account_positions(host: &str, account: &str, page: u32, per_page: u32) -> String {
+    format!("{host}/private/positions?account={account}&page={page}&per_page={per_page}")
 }
 
 #[inline(always)]
-pub fn watchlists() -> String {
-    format!("{FT_HOST}/private/watchlists")
+pub fn watchlists(host: &str) -> String {
+    format!("{host}/private/watchlists")
 }
 
 #[inline(always)]
-pub fn watchlist() -> String {
-    format!("{FT_HOST}/private/watchlist")
+pub fn watchlist(host: &str) -> String {
+    format!("{host}/private/watchlist")
 }
 
 #[inline(always)]
-pub fn watchlist_quote(id: u32) -> String {
-    format!("{FT_HOST}/private/watchlists/{id}")
+pub fn watchlist_quote(host: &str, id: u32) -> String {
+    format!("{host}/private/watchlists/{id}")
 }
 
 #[inline(always)]
-pub fn single_quote(account: &str, symbol: &str) -> String {
-    format!("{FT_HOST}/public/quote?account={account}&q={symbol}")
+pub fn single_quote(host: &str, account: &str, symbol: &str) -> String {
+    format!("{host}/public/quote?account={account}&q={}", encode(symbol))
 }
 
 #[inline(always)]
-pub fn fundamental(symbol: &str) -> String {
-    format!("{FT_HOST}/public/fundamental?symbol={symbol}")
+pub fn fundamental(host: &str, symbol: &str) -> String {
+    format!("{host}/public/fundamental?symbol={}", encode(symbol))
 }
 
 #[inline(always)]
-pub fn company_profile(symbol: &str) -> String {
-    format!("{FT_HOST}/public/company_profile?q={symbol}")
+pub fn company_profile(host: &str, symbol: &str) -> String {
+    format!("{host}/public/company_profile?q={}", encode(symbol))
 }
 
 #[inline(always)]
-pub fn cash_dividend(symbol: &str) -> String {
-    format!("{FT_HOST}/private/cash_dividend?q={symbol}")
+pub fn cash_dividend(host: &str, symbol: &str) -> String {
+    format!("{host}/private/cash_dividend?q={}", encode(symbol))
 }
 
 #[inline(always)]
-pub fn corp_calendar(symbol: &str) -> String {
-    format!("{FT_HOST}/private/corp_calendar?q={symbol}")
+pub fn corp_calendar(host: &str, symbol: &str) -> String {
+    format!("{host}/private/corp_calendar?q={}", encode(symbol))
 }
 
-// NOTE: symbols should be a comma-separated list of symbols
-// e.g. "AAPL,GOOGL,MSFT"
-// resolution must be less than or equal to 50
+// NOTE: symbols are sent comma-separated, e.g. "AAPL,GOOGL,MSFT", resolution is checked by `Resolution`
 #[inline(always)]
-pub fn stocks_mohlc(symbols: &str, resolution: u8) -> String {
-    format!("{FT_HOST}/public/mohlc?symbols={symbols}&resolution={resolution}")
+pub fn stocks_mohlc<S: AsRef<str>>(host: &str, symbols: &[S], resolution: u8) -> String {
+    let symbols: Vec<String> = symbols.iter().map(|s| encode(s.as_ref()).to_string()).collect();
+    format!(
+        "{host}/public/mohlc?symbols={}&resolution={resolution}",
+        symbols.join(",")
+    )
+}
+
+// NOTE: range must be one of [1h, 1d, 24h, 1w, 1m, 3m, 1y, 5y, ytd, all], see `OhlcRange`
+#[inline(always)]
+pub fn stock_ohlc(host: &str, symbol: &str, range: &str) -> String {
+    format!("{host}/public/ohlc?symbol={}&range={range}&_v=v2", encode(symbol))
 }
 
-// NOTE: range must be one of [1h, 1d, 24h, 1w, 1m, 3m, 1y, 5y, ytd, all]
 #[inline(always)]
-pub fn stock_ohlc(symbols: &str, range: &str) -> String {
-    format!("{FT_HOST}/public/ohlc?symbol={symbols}&range={range}&_v=v2")
+pub fn stock_order(host: &str) -> String {
+    format!("{host}/private/stock_order")
+}
+
+#[inline(always)]
+pub fn option_order(host: &str) -> String {
+    format!("{host}/private/option_order")
+}
+
+// NOTE: legs are sent as numbered fields, e.g. `symbol1`, `transaction1`, `ratio1`
+#[inline(always)]
+pub fn multi_leg_order(host: &str) -> String {
+    format!("{host}/private/option_spread_order")
+}
+
+#[inline(always)]
+pub fn order_status(host: &str, account: &str) -> String {
+    format!("{host}/private/order_status?account={account}")
+}
+
+#[inline(always)]
+pub fn cancel_order(host: &str) -> String {
+    format!("{host}/private/cancel_order")
+}
+
+// NOTE: takes the stock order form plus `orig_order_id`
+#[inline(always)]
+pub fn replace_order(host: &str) -> String {
+    format!("{host}/private/replace_order")
+}
+
+#[inline(always)]
+pub fn option_expirations(host: &str, symbol: &str) -> String {
+    format!("{host}/public/oc?m=get_exp_dates&root_symbol={}", encode(symbol))
+}
+
+// NOTE: exp_date is `YYYYMMDD`, chains_range `A` returns every strike
+#[inline(always)]
+pub fn option_chain(host: &str, symbol: &str, exp_date: &str) -> String {
+    format!(
+        "{host}/public/oc?m=get_oc&root_symbol={}&exp_date={exp_date}&chains_range=A",
+        encode(symbol)
+    )
+}
+
+// NOTE: takes the same `root_symbol`, `exp_date` and `chains_range` as `option_chain` in a form body
+#[inline(always)]
+pub fn option_greeks(host: &str) -> String {
+    format!("{host}/private/greekoptions/analytical")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_encoding() {
+        let host = "https://api3x.firstrade.com";
+        assert_eq!(
+            single_quote(host, "12345678", "BRK.B"),
+            "https://api3x.firstrade.com/public/quote?account=12345678&q=BRK.B"
+        );
+        assert_eq!(
+            stock_ohlc(host, "BF/B&x=1", "1d"),
+            "https://api3x.firstrade.com/public/ohlc?symbol=BF%2FB%26x%3D1&range=1d&_v=v2"
+        );
+        assert_eq!(
+            stocks_mohlc(host, &["AAPL", "BRK.B", "BF/B"], 5),
+            "https://api3x.firstrade.com/public/mohlc?symbols=AAPL,BRK.B,BF%2FB&resolution=5"
+        );
+    }
 }
@@ -1,7 +1,7 @@
+use crate::endpoint::Endpoint;
 use crate::error::{Error, ErrorKind, Result};
 use crate::models::session::ErrorResponse;
 use crate::session::FtCreds;
-use crate::url::{ACCESS_TOKEN, USER_AGENT};
 use axum::http::HeaderMap;
 use http::HeaderValue;
 use http::header::InvalidHeaderValue;
@@ -10,11 +10,12 @@ use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 use std::time::Duration;
 
-pub(crate) fn build_default_https_client() -> HttpClient {
+pub(crate) fn build_default_https_client(endpoint: &Endpoint) -> HttpClient {
     let mut headers = HeaderMap::new();
     headers.insert("Connection", "Keep-Alive".parse().unwrap());
-    headers.insert("User-Agent", USER_AGENT.parse().unwrap());
-    headers.insert("access-token", ACCESS_TOKEN.parse().unwrap());
+    // NOTE: both are validated by the `Endpoint` setters
+    headers.insert("User-Agent", endpoint.user_agent().parse().unwrap());
+    headers.insert("access-token", endpoint.access_token().parse().unwrap());
 
     HttpClient::builder()
         .default_headers(headers)
@@ -33,6 +34,33 @@ pub(crate) fn login_credential_error(field: &str) -> Error {
     Error::new(ErrorKind::LoginFailed, format!("missing field: {field}"))
 }
 
+#[inline]
+pub(crate) fn missing_password_error() -> Error {
+    Error::new(
+        ErrorKind::ConfigInvalid,
+        "no password to log in with, credentials resumed without one can't be refreshed",
+    )
+}
+
+#[inline]
+pub(crate) fn order_rejected_error(error: &str, message: &str) -> Error {
+    Error::new(ErrorKind::OrderRejected, "order rejected")
+        .with_context("error", error)
+        .with_context("message", message)
+}
+
+#[inline]
+pub(crate) fn option_chain_error(error: &str, message: &str) -> Error {
+    Error::new(ErrorKind::NotFound, "option chain not available")
+        .with_context("error", error)
+        .with_context("message", message)
+}
+
+#[inline]
+pub(crate) fn form_body<'a>(form: &'a HashMap<&'static str, String>) -> HashMap<&'a str, &'a str> {
+    form.iter().map(|(k, v)| (*k, v.as_str())).collect()
+}
+
 #[inline]
 pub(crate) fn parse_reqwest_error(err: reqwest::Error) -> Error {
     Error::new(ErrorKind::Unexpected, err.to_string()).set_source(err)
@@ -64,11 +92,7 @@ pub(crate) async fn handle_failed_response(resp: Response) -> Error {
         .unwrap_or_else(|_| (body.to_string(), None));
 
     if let Some(login_err) = login_err {
-        kind = match login_err.error.as_str() {
-            "Unauthorized" => ErrorKind::Unauthorized,
-            "Forbidden" => ErrorKind::Forbidden,
-            _ => ErrorKind::Unexpected,
-        };
+        kind = error_body_kind(&login_err);
     }
 
     Error::new(kind, "request failed")
@@ -76,6 +100,31 @@ pub(crate) async fn handle_failed_response(resp: Response) -> Error {
         .with_context("message", message)
 }
 
+#[inline]
+fn error_body_kind(err: &ErrorResponse) -> ErrorKind {
+    match err.error.as_str() {
+        "Unauthorized" => ErrorKind::Unauthorized,
+        "Forbidden" => ErrorKind::Forbidden,
+        _ if err.status_code.is_server_error() => ErrorKind::ServerError,
+        _ => ErrorKind::Unexpected,
+    }
+}
+
+// NOTE: a 200 body that doesn't match `T` is usually an error body, e.g. an expired session
+pub(crate) async fn parse_success_response<T: DeserializeOwned>(resp: Response) -> Result<T> {
+    let url = resp.url().clone();
+    let body = resp.text().await.map_err(parse_reqwest_error)?;
+
+    serde_json::from_str(&body).map_err(|e| match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(err_body) if !err_body.error.is_empty() => {
+            Error::new(error_body_kind(&err_body), "request failed")
+                .with_context("url", format!("{url:?}"))
+                .with_context("message", format!("{err_body:?}"))
+        }
+        _ => parse_json_error(e).with_context("url", format!("{url:?}")),
+    })
+}
+
 pub(crate) async fn get_with_auth<T: DeserializeOwned>(
     client: &HttpClient,
     url: String,
@@ -96,9 +145,7 @@ pub(crate) async fn get_with_auth<T: DeserializeOwned>(
         return Err(handle_failed_response(response).await);
     }
 
-    let body = response.text().await.map_err(parse_reqwest_error)?;
-    let data = serde_json::from_str(&body).map_err(parse_json_error)?;
-    Ok(data)
+    parse_success_response(response).await
 }
 
 pub(crate) async fn post_with_auth<T: DeserializeOwned>(
@@ -127,9 +174,7 @@ pub(crate) async fn post_with_auth<T: DeserializeOwned>(
         return Err(handle_failed_response(response).await);
     }
 
-    let body = response.text().await.map_err(parse_reqwest_error)?;
-    let data = serde_json::from_str(&body).map_err(parse_json_error)?;
-    Ok(data)
+    parse_success_response(response).await
 }
 
 pub(crate) async fn delete_with_auth<T: DeserializeOwned>(
@@ -151,7 +196,5 @@ pub(crate) async fn delete_with_auth<T: DeserializeOwned>(
         return Err(handle_failed_response(response).await);
     }
 
-    let body = response.text().await.map_err(parse_reqwest_error)?;
-    let data = serde_json::from_str(&body).map_err(parse_json_error)?;
-    Ok(data)
+    parse_success_response(response).await
 }
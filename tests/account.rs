@@ -0,0 +1,416 @@
+mod common;
+
+use common::{account, account_with, endpoint, symbol};
+use firstrade::account::{FtAccount, RefreshPolicy};
+use firstrade::error::ErrorKind;
+use firstrade::models::account::Instrument;
+use firstrade::models::quote::{DEFAULT_MOHLC_CHUNK_SIZE, OhlcRange, QuoteResult, Resolution};
+use firstrade::models::security::SecurityType;
+use firstrade::session::{FtSession, FtSessionBuilder, FtSessionConfig};
+use firstrade::store::{CredentialStore, FileCredentialStore, StoredCreds};
+use firstrade_mock::{Failure, MockConfig, MockServer, fixtures};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const MFA_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+async fn login(server: &MockServer) -> FtAccount {
+    let mut config = FtSessionConfig::default();
+    config
+        .set_username("user".to_string())
+        .set_password("pass".to_string())
+        .set_mfa_code("123456".to_string())
+        .set_endpoint(endpoint(server));
+
+    let mut session = FtSession::from_builder(FtSessionBuilder::new(config).unwrap());
+    session.login().await.unwrap();
+    FtAccount::from_session(session, server.config().account)
+}
+
+#[tokio::test]
+async fn test_account_info() {
+    let server = MockServer::start().await;
+    let account = login(&server).await;
+
+    let market_time = account.get_market_time().await.unwrap();
+    assert!(market_time.result.unwrap().is_trading_date);
+
+    let list = account.get_account_list().await.unwrap();
+    let items = list.items.unwrap();
+    assert_eq!(items[0].account, "12345678");
+
+    let user_info = account.get_user_info().await.unwrap();
+    assert_eq!(user_info.selected_account, "12345678");
+
+    let balances = account.get_account_balances().await.unwrap();
+    assert_eq!(balances.result.unwrap().account, "12345678");
+
+    let positions = account.get_account_positions().await.unwrap();
+    assert_eq!(positions.total, 3);
+    assert_eq!(positions.items.len(), 3);
+    assert_eq!(positions.items[2].sec_type, SecurityType::Option);
+    assert_eq!(positions.items[0].instrument, Instrument::Equity);
+    match &positions.items[2].instrument {
+        Instrument::Option {
+            symbol, multiplier, ..
+        } => {
+            assert_eq!(symbol.root, "ABCD");
+            assert_eq!(*multiplier, 100);
+        }
+        other => panic!("expected an option, got {other:?}"),
+    }
+    assert_eq!(positions.items[2].symbol, "ABCD260116C00003000");
+
+    let history = account.get_account_history("ytd", 1, 2).await.unwrap();
+    assert_eq!(history.total, 5);
+    assert_eq!(history.items.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_positions_pages() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let positions: Vec<_> = (0..450)
+        .map(|i| fixtures::position(&format!("T{i}"), 1, 10, 10.0 + i as f64, 9.0))
+        .chain([fixtures::position("ABCD260116C00003000", 2, 2, 0.9, 1.0)])
+        .collect();
+    server.state().set_positions(positions);
+    let expected = server.state().request_count("/private/positions") + 3;
+
+    let merged = account.get_account_positions().await.unwrap();
+    assert_eq!(server.state().request_count("/private/positions"), expected);
+    assert_eq!(merged.items.len(), 451);
+    assert_eq!((merged.total, merged.pages), (451, 1));
+    assert_eq!(merged.items[450].symbol, "ABCD260116C00003000");
+    assert!(merged.items[450].instrument.is_option());
+
+    // Totals cover every page, same as the server computes them for the whole account
+    let whole = account.get_account_positions_page(1, 1000).await.unwrap();
+    assert_eq!(whole.items.len(), 451);
+    assert_eq!(merged.total_market_value, whole.total_market_value);
+    assert_eq!(merged.total_gainloss, whole.total_gainloss);
+    assert!((merged.total_gainloss_percent - whole.total_gainloss_percent).abs() < 1e-3);
+
+    let page = account.get_account_positions_page(3, 200).await.unwrap();
+    assert_eq!((page.page, page.pages, page.total), (3, 3, 451));
+    assert_eq!(page.items.len(), 51);
+}
+
+#[tokio::test]
+async fn test_quotes() {
+    let server = MockServer::start().await;
+    let account = login(&server).await;
+
+    let quote = account.get_single_quote("AAPL".to_string()).await.unwrap();
+    assert!(matches!(quote.result, Some(QuoteResult::Stock(q)) if q.symbol == "AAPL"));
+
+    let quote = account
+        .get_single_quote("ABCD260116C00003000".to_string())
+        .await
+        .unwrap();
+    assert!(matches!(quote.result, Some(QuoteResult::Option(q)) if q.underlying_symbol == "ABCD"));
+
+    let quote = account.get_single_quote("NOPE".to_string()).await.unwrap();
+    assert!(quote.result.is_none());
+    assert!(!quote.error.is_empty());
+
+    let ohlc = account
+        .get_stock_ohlc("AAPL".to_string(), OhlcRange::OneDay)
+        .await
+        .unwrap();
+    let ohlc = ohlc.result.unwrap();
+    assert_eq!(ohlc.ohlc.len(), 5);
+    assert_eq!(ohlc.ohlc[0].5, Some(1000));
+
+    let symbols = [symbol("AAPL"), symbol("msft"), symbol("BRK.B"), symbol("NOPE")];
+    let mohlc = account
+        .get_stocks_mohlc(&symbols, Resolution::new(3).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(mohlc.len(), 3);
+    assert_eq!(mohlc["MSFT"].ohlc.len(), 3);
+    assert!(mohlc.contains_key("BRK.B"));
+}
+
+#[tokio::test]
+async fn test_batch_quotes() {
+    let server = MockServer::start().await;
+    let account = account(&server).with_quote_concurrency(2);
+
+    let symbols = [symbol("AAPL"), symbol("NOPE"), symbol("MSFT"), symbol("aapl")];
+    let batch = account.get_quotes(&symbols).await;
+    assert_eq!(batch.quotes.len(), 3);
+    assert_eq!(batch.quotes[1].0, symbol("NOPE"));
+    assert_eq!(
+        batch.get(&symbol("NOPE")).unwrap().as_ref().unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    assert!(matches!(batch.get(&symbol("MSFT")), Some(Ok(QuoteResult::Stock(q))) if q.symbol == "MSFT"));
+    assert_eq!(batch.successes().count(), 2);
+
+    let stats = batch.stats;
+    assert_eq!((stats.requested, stats.succeeded, stats.failed), (3, 2, 1));
+    assert!(stats.min_latency <= stats.mean_latency && stats.mean_latency <= stats.max_latency);
+    assert!(stats.max_latency <= stats.elapsed);
+    assert_eq!(server.state().request_count("/public/quote"), 3);
+}
+
+#[tokio::test]
+async fn test_mohlc_chunks() {
+    let server = MockServer::start_with(MockConfig {
+        mohlc_max_symbols: Some(5),
+        ..Default::default()
+    })
+    .await;
+    let account = account(&server).with_mohlc_chunk_size(5);
+
+    let mut symbols = vec![symbol("AAPL")];
+    for i in 0..10 {
+        let name = format!("T{i}");
+        server
+            .state()
+            .set_quote(&name, fixtures::stock_quote(&name, 10.0));
+        symbols.push(symbol(&name));
+    }
+    // Duplicates are only requested once
+    symbols.push(symbol("aapl"));
+
+    let mohlc = account
+        .get_stocks_mohlc(&symbols, Resolution::new(2).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(mohlc.len(), 11);
+    assert_eq!(server.state().request_count("/public/mohlc"), 3);
+
+    // Chunks beyond what the server takes fail
+    let err = account
+        .clone()
+        .with_mohlc_chunk_size(DEFAULT_MOHLC_CHUNK_SIZE)
+        .get_stocks_mohlc(&symbols, Resolution::new(2).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unexpected);
+
+    server.state().fail_next("/public/mohlc", Failure::Status(503));
+    let err = account
+        .get_stocks_mohlc(&symbols, Resolution::new(2).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ServerError);
+}
+
+#[tokio::test]
+async fn test_watchlist_crud() {
+    let server = MockServer::start().await;
+    let account = login(&server).await;
+
+    let lists = account.get_all_watchlists().await.unwrap().items.unwrap();
+    assert_eq!(lists.len(), 1);
+    assert!(lists[0].is_default);
+
+    let added = account.add_new_watchlist("Tech".to_string()).await.unwrap();
+    let Some(firstrade::models::watchlist::WatchListResult::AddNewWatchList(list)) = added.result else {
+        panic!("unexpected add watchlist result");
+    };
+    let list_id = list.list_id as u32;
+
+    account
+        .watchlist_add_symbol(list_id, "AAPL".to_string(), SecurityType::Equity)
+        .await
+        .unwrap();
+    let quote = account
+        .get_watchlist_quote(list_id)
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert_eq!(quote.name, "Tech");
+    assert_eq!(quote.list_items.len(), 1);
+    assert_eq!(quote.list_items[0].symbol, "AAPL");
+
+    let item_id = quote.list_items[0].watchlist_id as u32;
+    account.watchlist_remove_symbol(item_id).await.unwrap();
+    let quote = account
+        .get_watchlist_quote(list_id)
+        .await
+        .unwrap()
+        .result
+        .unwrap();
+    assert!(quote.list_items.is_empty());
+
+    account.delete_watchlist(list_id).await.unwrap();
+    assert_eq!(
+        account.get_all_watchlists().await.unwrap().items.unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_failed_responses() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    server
+        .state()
+        .fail_next("/private/balances", Failure::Status(503));
+    let err = account.get_account_balances().await.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ServerError);
+
+    server
+        .state()
+        .fail_next("/private/balances", Failure::Unauthorized);
+    let err = account.get_account_balances().await.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unauthorized);
+
+    // Firstrade also reports errors in 200 responses
+    server.state().fail_next(
+        "/private/positions",
+        Failure::ErrorBody {
+            status_code: 401,
+            error: "Unauthorized".to_string(),
+            message: "Session expired".to_string(),
+        },
+    );
+    let err = account.get_account_positions().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unauthorized);
+
+    // Without a refresh policy an expired session is returned to the caller
+    server.state().expire_sid();
+    let err = account.get_account_balances().await.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::Unauthorized);
+    assert_eq!(server.state().request_count("/sess/login"), 0);
+}
+
+#[tokio::test]
+async fn test_refresh_renews_sid() {
+    let server = MockServer::start().await;
+    let account = account(&server).with_refresh_policy(RefreshPolicy::RenewSid);
+    let stale_sid = account.get_sid().await;
+
+    server.state().expire_sid();
+    account.get_account_balances().await.unwrap();
+    assert_eq!(server.state().request_count("/sess/login"), 1);
+    assert_ne!(account.get_sid().await, stale_sid);
+    assert_eq!(Some(account.get_sid().await), server.state().sid());
+}
+
+#[tokio::test]
+async fn test_refresh_is_single_flight() {
+    let server = MockServer::start().await;
+    let account = account(&server).with_refresh_policy(RefreshPolicy::RenewSid);
+
+    server.state().expire_sid();
+    let tasks: Vec<_> = (0..8)
+        .map(|_| {
+            let account = account.clone();
+            tokio::spawn(async move { account.get_account_balances().await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+    assert_eq!(server.state().request_count("/sess/login"), 1);
+}
+
+#[tokio::test]
+async fn test_refresh_with_totp() {
+    let server = MockServer::start_with(MockConfig {
+        mfa_code: None,
+        ..Default::default()
+    })
+    .await;
+    let account = account_with(&server, Some(MFA_SECRET)).with_refresh_policy(RefreshPolicy::RenewSid);
+
+    server.state().expire_ftat();
+    account.get_account_positions().await.unwrap();
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 1);
+}
+
+#[tokio::test]
+async fn test_refresh_with_callback() {
+    let server = MockServer::start().await;
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let policy = RefreshPolicy::RenewSidOrCallback(Arc::new(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async { Ok("123456".to_string()) })
+    }));
+    let account = account(&server).with_refresh_policy(policy);
+
+    server.state().expire_ftat();
+    account.get_account_positions().await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // Without the callback an expired ftat cannot be recovered
+    let account = account.with_refresh_policy(RefreshPolicy::RenewSid);
+    server.state().expire_ftat();
+    assert!(account.get_account_positions().await.is_err());
+}
+
+#[tokio::test]
+async fn test_account_from_store() {
+    let server = MockServer::start().await;
+    let path = std::env::temp_dir().join(format!("firstrade-{}-account-store.json", std::process::id()));
+    let store = Arc::new(FileCredentialStore::new(path, "passphrase".to_string()).with_login(true));
+    let err = FtAccount::from_store(store.clone(), server.config().account, endpoint(&server))
+        .err()
+        .unwrap();
+    assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+
+    let (ftat, sid) = server.state().issue_session();
+    store
+        .save(&StoredCreds {
+            ftat,
+            sid,
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        })
+        .unwrap();
+    let account = FtAccount::from_store(store.clone(), server.config().account, endpoint(&server))
+        .unwrap()
+        .with_refresh_policy(RefreshPolicy::RenewSid);
+    account.get_account_balances().await.unwrap();
+
+    // The renewed sid is saved back to the store it was loaded from
+    server.state().expire_sid();
+    account.get_account_balances().await.unwrap();
+    assert_eq!(store.load().unwrap().unwrap().sid, account.get_sid().await);
+    store.clear().unwrap();
+}
+
+#[tokio::test]
+async fn test_refresh_without_password() {
+    let server = MockServer::start().await;
+    let path = std::env::temp_dir().join(format!("firstrade-{}-no-password.json", std::process::id()));
+    let store = Arc::new(FileCredentialStore::new(path, "passphrase".to_string()));
+    let (ftat, sid) = server.state().issue_session();
+    store
+        .save(&StoredCreds {
+            ftat,
+            sid,
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+        })
+        .unwrap();
+
+    // The store drops the password, so the session can be used but not renewed
+    let account = FtAccount::from_store(store.clone(), server.config().account, endpoint(&server))
+        .unwrap()
+        .with_refresh_policy(RefreshPolicy::RenewSid);
+    account.get_account_balances().await.unwrap();
+    assert_eq!(
+        account.renew_sid().await.unwrap_err().kind(),
+        ErrorKind::ConfigInvalid
+    );
+    assert_eq!(
+        account.re_login("123456".to_string()).await.unwrap_err().kind(),
+        ErrorKind::ConfigInvalid
+    );
+
+    server.state().expire_sid();
+    let err = account.get_account_balances().await.err().unwrap();
+    assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+    assert_eq!(server.state().request_count("/sess/login"), 0);
+    store.clear().unwrap();
+}
@@ -0,0 +1,42 @@
+//! Helpers shared by the integration tests, each test crate uses a subset of them.
+
+use firstrade::account::{FtAccount, FtAccountConfig};
+use firstrade::endpoint::Endpoint;
+use firstrade::models::money::Money;
+use firstrade::models::security::Symbol;
+use firstrade_mock::MockServer;
+
+#[allow(dead_code)]
+pub fn symbol(symbol: &str) -> Symbol {
+    Symbol::new(symbol).unwrap()
+}
+
+#[allow(dead_code)]
+pub fn money(amount: &str) -> Money {
+    amount.parse().unwrap()
+}
+
+pub fn endpoint(server: &MockServer) -> Endpoint {
+    Endpoint::new(&server.base_url()).unwrap()
+}
+
+/// An account on a session freshly issued by the mock, no login round trip needed.
+pub fn account(server: &MockServer) -> FtAccount {
+    account_with(server, None)
+}
+
+pub fn account_with(server: &MockServer, mfa_secret: Option<&str>) -> FtAccount {
+    let (ftat, sid) = server.state().issue_session();
+    let config = FtAccountConfig::builder()
+        .username("user".to_string())
+        .password("pass".to_string())
+        .ftat(ftat)
+        .sid(sid)
+        .account_id(server.config().account)
+        .client(None)
+        .endpoint(endpoint(server));
+    match mfa_secret {
+        Some(secret) => FtAccount::new(config.mfa_secret(secret.to_string()).build()),
+        None => FtAccount::new(config.build()),
+    }
+}
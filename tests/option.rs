@@ -0,0 +1,55 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::account;
+use firstrade::error::ErrorKind;
+use firstrade_mock::{Failure, MockServer};
+
+#[tokio::test]
+async fn test_option_chain() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let expirations = account.get_option_expirations("msft").await.unwrap();
+    assert_eq!(expirations.len(), 3);
+    let expiration = expirations[0].exp_date;
+    assert_eq!(expiration, NaiveDate::from_ymd_opt(2025, 9, 19).unwrap());
+
+    let chain = account.get_option_chain("MSFT", expiration).await.unwrap();
+    assert_eq!(chain.strikes.len(), 5);
+    assert_eq!(chain.calls().count(), 5);
+    let strike = chain.strike("510".parse().unwrap()).unwrap();
+    let call = strike.call.as_ref().unwrap();
+    let put = strike.put.as_ref().unwrap();
+    assert_eq!(call.symbol.to_string(), "MSFT250919C00510000");
+    assert_eq!(put.symbol.to_string(), "MSFT250919P00510000");
+    assert_eq!(call.greeks.unwrap().delta, 0.5);
+    assert_eq!(put.greeks.unwrap().delta, -0.5);
+}
+
+#[tokio::test]
+async fn test_option_chain_without_greeks() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    server
+        .state()
+        .fail_next("/private/greekoptions/analytical", Failure::Status(503));
+    let expiration = NaiveDate::from_ymd_opt(2025, 9, 26).unwrap();
+    let chain = account.get_option_chain("AAPL", expiration).await.unwrap();
+    assert_eq!(chain.strikes.len(), 5);
+    assert!(chain.calls().chain(chain.puts()).all(|c| c.greeks.is_none()));
+}
+
+#[tokio::test]
+async fn test_option_chain_not_found() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let err = account.get_option_expirations("NOPE").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    let expiration = NaiveDate::from_ymd_opt(2025, 9, 20).unwrap();
+    let err = account.get_option_chain("AAPL", expiration).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
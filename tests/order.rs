@@ -0,0 +1,354 @@
+mod common;
+
+use common::{account, money};
+use firstrade::error::ErrorKind;
+use firstrade::models::option::OccSymbol;
+use firstrade::models::order::{
+    Duration, MultiLegOrder, NetPrice, OptionAction, OptionOrder, OrderChange, OrderSide, OrderState,
+    OrderType, StockOrder,
+};
+use firstrade_mock::{MockConfig, MockServer, fixtures};
+
+fn occ(symbol: &str) -> OccSymbol {
+    symbol.parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_place_stock_order() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let order = StockOrder::builder()
+        .symbol("AAPL")
+        .side(OrderSide::Buy)
+        .quantity(10)
+        .order_type(OrderType::Limit {
+            limit_price: money("38.5"),
+        })
+        .duration(Duration::GoodTillCanceled)
+        .build();
+    let preview = account.preview_stock_order(order).await.unwrap();
+    assert_eq!(preview.estimated_cost(), money("385"));
+    assert_eq!(preview.commission(), money("0"));
+    assert!(server.state().orders().is_empty());
+
+    let confirmation = account.place_stock_order(preview).await.unwrap();
+    assert_eq!(confirmation.result, "success");
+
+    let orders = server.state().orders();
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].order_id, confirmation.order_id);
+    assert_eq!(orders[0].price_type, "2");
+    assert_eq!(orders[0].limit_price, Some(38.5));
+    assert_eq!(orders[0].duration, "1");
+}
+
+#[tokio::test]
+async fn test_stock_order_rejected() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    // Validated before anything is sent
+    let order = StockOrder::builder()
+        .symbol("AAPL")
+        .side(OrderSide::Buy)
+        .quantity(0)
+        .order_type(OrderType::Market)
+        .build();
+    let err = account.preview_stock_order(order).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+    assert_eq!(server.state().request_count("/private/stock_order"), 0);
+
+    let order = StockOrder::builder()
+        .symbol("MSFT")
+        .side(OrderSide::Buy)
+        .quantity(1000)
+        .order_type(OrderType::Market)
+        .build();
+    let err = account.preview_stock_order(order).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OrderRejected);
+    assert!(server.state().orders().is_empty());
+}
+
+fn limit_order(symbol: &str, quantity: u32, limit_price: &str) -> StockOrder {
+    StockOrder::builder()
+        .symbol(symbol)
+        .side(OrderSide::Buy)
+        .quantity(quantity)
+        .order_type(OrderType::Limit {
+            limit_price: money(limit_price),
+        })
+        .build()
+}
+
+#[tokio::test]
+async fn test_order_status() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let preview = account
+        .preview_stock_order(limit_order("AAPL", 10, "38.5"))
+        .await
+        .unwrap();
+    let order_id = account.place_stock_order(preview).await.unwrap().order_id;
+
+    let order = account.get_order(&order_id).await.unwrap();
+    assert_eq!(order.status, OrderState::Open);
+    assert_eq!(order.transaction, OrderSide::Buy);
+    assert_eq!(order.executed_quantity, 0.0);
+
+    assert!(server.state().fill_order(&order_id, 4.0, 38.4));
+    assert!(server.state().fill_order(&order_id, 2.0, 38.5));
+    let order = account.get_order(&order_id).await.unwrap();
+    assert_eq!(order.status, OrderState::PartiallyFilled);
+    assert_eq!(order.executed_quantity, 6.0);
+    assert_eq!(order.remaining_quantity(), 4.0);
+    assert_eq!(order.fills.len(), 2);
+    assert!((order.avg_fill_price.unwrap() - money("38.4333")).abs() < money("0.001"));
+
+    // Market orders fill right away in the mock
+    let order = StockOrder::builder()
+        .symbol("MSFT")
+        .side(OrderSide::Sell)
+        .quantity(2)
+        .order_type(OrderType::Market)
+        .build();
+    let preview = account.preview_stock_order(order).await.unwrap();
+    let market_id = account.place_stock_order(preview).await.unwrap().order_id;
+
+    let orders = account.list_orders().await.unwrap().items.unwrap();
+    assert_eq!(orders.len(), 2);
+    let market = orders.iter().find(|o| o.order_id == market_id).unwrap();
+    assert_eq!(market.status, OrderState::Filled);
+    assert!(!market.status.is_open());
+
+    let preview = account
+        .preview_stock_order(limit_order("AAPL", 1, "30"))
+        .await
+        .unwrap();
+    let rejected_id = account.place_stock_order(preview).await.unwrap().order_id;
+    server
+        .state()
+        .reject_order(&rejected_id, "Price too far from market");
+    let rejected = account.get_order(&rejected_id).await.unwrap();
+    assert_eq!(rejected.status, OrderState::Rejected);
+    assert_eq!(
+        rejected.reject_reason.as_deref(),
+        Some("Price too far from market")
+    );
+
+    let err = account.get_order("404").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
+
+#[tokio::test]
+async fn test_cancel_order() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let preview = account
+        .preview_stock_order(limit_order("AAPL", 10, "38.5"))
+        .await
+        .unwrap();
+    let order_id = account.place_stock_order(preview).await.unwrap().order_id;
+
+    let confirmation = account.cancel_order(&order_id).await.unwrap();
+    assert_eq!(confirmation.order_id, order_id);
+    let order = account.get_order(&order_id).await.unwrap();
+    assert_eq!(order.status, OrderState::Cancelled);
+
+    // Already cancelled
+    let err = account.cancel_order(&order_id).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OrderRejected);
+}
+
+#[tokio::test]
+async fn test_replace_order() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let preview = account
+        .preview_stock_order(limit_order("AAPL", 10, "38.5"))
+        .await
+        .unwrap();
+    let order_id = account.place_stock_order(preview).await.unwrap().order_id;
+    server.state().fill_order(&order_id, 4.0, 38.5);
+
+    let change = OrderChange::builder()
+        .order_type(OrderType::Limit {
+            limit_price: money("39"),
+        })
+        .build();
+    let preview = account.preview_replace_order(&order_id, change).await.unwrap();
+    assert_eq!(preview.estimated_cost(), money("390"));
+    assert_eq!(preview.order().order.quantity, 10);
+
+    let confirmation = account.replace_order(preview).await.unwrap();
+    assert_eq!(confirmation.original_order_id, order_id);
+    assert_ne!(confirmation.order_id, order_id);
+
+    let original = account.get_order(&order_id).await.unwrap();
+    assert_eq!(original.status, OrderState::Replaced);
+    let replacement = account.get_order(&confirmation.order_id).await.unwrap();
+    assert_eq!(replacement.status, OrderState::PartiallyFilled);
+    assert_eq!(replacement.limit_price, Some(money("39")));
+    assert_eq!(replacement.executed_quantity, 4.0);
+
+    // The original is no longer open
+    let change = OrderChange::builder().quantity(20).build();
+    let err = account
+        .preview_replace_order(&order_id, change)
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConditionNotMatch);
+    assert_eq!(server.state().request_count("/private/replace_order"), 2);
+}
+
+#[tokio::test]
+async fn test_place_option_order() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let order = OptionOrder::builder()
+        .symbol(occ("ABCD260116C00003000"))
+        .action(OptionAction::BuyToOpen)
+        .contracts(2)
+        .order_type(OrderType::Limit {
+            limit_price: money("0.85"),
+        })
+        .build();
+    let preview = account.preview_option_order(order).await.unwrap();
+    assert_eq!(preview.estimated_cost(), money("170"));
+
+    let confirmation = account.place_option_order(preview).await.unwrap();
+    let order = account.get_order(&confirmation.order_id).await.unwrap();
+    assert_eq!(order.transaction, OrderSide::BuyToOpen);
+    assert_eq!(order.quantity, 2.0);
+
+    // Unknown contracts are refused by the server
+    let order = OptionOrder::builder()
+        .symbol(occ("ABCD260116P00003000"))
+        .action(OptionAction::SellToClose)
+        .contracts(1)
+        .order_type(OrderType::Market)
+        .build();
+    let err = account.preview_option_order(order).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OrderRejected);
+}
+
+#[tokio::test]
+async fn test_option_level_too_low() {
+    let server = MockServer::start_with(MockConfig {
+        option_level: 1,
+        ..Default::default()
+    })
+    .await;
+    let account = account(&server);
+
+    let order = OptionOrder::builder()
+        .symbol(occ("ABCD260116C00003000"))
+        .action(OptionAction::BuyToOpen)
+        .contracts(1)
+        .order_type(OrderType::Market)
+        .build();
+    let err = account.preview_option_order(order.clone()).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OrderRejected);
+    assert_eq!(server.state().request_count("/private/option_order"), 0);
+
+    // Closing is allowed at level 1
+    let order = OptionOrder {
+        action: OptionAction::SellToClose,
+        ..order
+    };
+    account.preview_option_order(order.clone()).await.unwrap();
+
+    // Whether a sale to open is covered is left to the server
+    let accounts = server.state().request_count("/private/acct_list");
+    let order = OptionOrder {
+        action: OptionAction::SellToOpen,
+        ..order
+    };
+    account.preview_option_order(order).await.unwrap();
+    assert_eq!(server.state().request_count("/private/acct_list"), accounts);
+}
+
+fn add_spy_options(server: &MockServer) {
+    for (symbol, call, strike, last) in [
+        ("SPY250919P00590000", false, 590.0, 4.1),
+        ("SPY250919C00600000", true, 600.0, 6.2),
+        ("SPY250919C00610000", true, 610.0, 2.9),
+    ] {
+        let quote = fixtures::option_quote(symbol, "SPY", call, strike, "20250919", last);
+        server.state().set_quote(symbol, quote);
+    }
+}
+
+#[tokio::test]
+async fn test_place_multi_leg_order() {
+    let server = MockServer::start_with(MockConfig {
+        option_level: 3,
+        ..Default::default()
+    })
+    .await;
+    add_spy_options(&server);
+    let account = account(&server);
+
+    let order = MultiLegOrder::vertical(
+        occ("SPY250919C00600000"),
+        occ("SPY250919C00610000"),
+        2,
+        NetPrice::Debit(money("3.1")),
+    );
+    let preview = account.preview_multi_leg_order(order).await.unwrap();
+    assert_eq!(preview.estimated_cost(), money("620"));
+
+    let confirmation = account.place_multi_leg_order(preview).await.unwrap();
+    let order = account.get_order(&confirmation.order_id).await.unwrap();
+    assert_eq!(order.symbol, "SPY");
+    assert_eq!(order.legs.len(), 2);
+    assert_eq!(order.legs[1].transaction, OrderSide::SellToOpen);
+
+    // A credit lowers the cost
+    let order = MultiLegOrder::vertical(
+        occ("SPY250919C00600000"),
+        occ("SPY250919C00610000"),
+        1,
+        NetPrice::Debit(money("3.1")),
+    )
+    .closing();
+    let preview = account.preview_multi_leg_order(order).await.unwrap();
+    assert_eq!(preview.estimated_cost(), money("-310"));
+}
+
+#[tokio::test]
+async fn test_multi_leg_order_level() {
+    let server = MockServer::start().await;
+    add_spy_options(&server);
+    let account = account(&server);
+
+    // Level 2 can buy a strangle but not sell one
+    let strangle = MultiLegOrder::strangle(
+        occ("SPY250919C00610000"),
+        occ("SPY250919P00590000"),
+        1,
+        NetPrice::Market,
+    );
+    account.preview_multi_leg_order(strangle.clone()).await.unwrap();
+
+    let err = account
+        .preview_multi_leg_order(strangle.short())
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::OrderRejected);
+    assert_eq!(server.state().request_count("/private/option_spread_order"), 1);
+
+    // Validated before the option level is fetched
+    let order = MultiLegOrder::straddle(
+        occ("SPY250919C00610000"),
+        occ("SPY250919P00590000"),
+        1,
+        NetPrice::Market,
+    );
+    let err = account.preview_multi_leg_order(order).await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ConfigInvalid);
+}
@@ -0,0 +1,289 @@
+use firstrade::endpoint::Endpoint;
+use firstrade::error::ErrorKind;
+use firstrade::session::{FtSession, FtSessionBuilder, FtSessionConfig, LoginState};
+use firstrade::store::{CredentialStore, FileCredentialStore, StoredCreds};
+use firstrade_mock::{LoginFlow, MockConfig, MockServer};
+use std::sync::Arc;
+
+// RFC 6238 test secret, "12345678901234567890" in base32
+const MFA_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+fn config(server: &MockServer) -> FtSessionConfig {
+    let mut config = FtSessionConfig::default();
+    config
+        .set_username("user".to_string())
+        .set_password("pass".to_string())
+        .set_endpoint(Endpoint::new(&server.base_url()).unwrap());
+    config
+}
+
+fn store(name: &str, ftat: String, username: &str) -> Arc<FileCredentialStore> {
+    let path = std::env::temp_dir().join(format!("firstrade-{}-{name}.json", std::process::id()));
+    let store = FileCredentialStore::new(path, "passphrase".to_string()).with_login(true);
+    store
+        .save(&StoredCreds {
+            ftat,
+            sid: String::new(),
+            username: Some(username.to_string()),
+            password: Some("pass".to_string()),
+        })
+        .unwrap();
+    Arc::new(store)
+}
+
+fn session(config: FtSessionConfig) -> FtSession {
+    FtSession::from_builder(FtSessionBuilder::new(config).unwrap())
+}
+
+#[tokio::test]
+async fn test_login_with_mfa_code() {
+    let server = MockServer::start().await;
+    let mut config = config(&server);
+    config.set_mfa_code("123456".to_string());
+
+    let mut session = session(config);
+    session.login().await.unwrap();
+
+    let creds = session.get_secrets().unwrap();
+    assert_eq!(Some(creds.get_ftat()), server.state().ftat());
+    assert_eq!(Some(creds.get_sid()), server.state().sid());
+    assert!(session.login_state().unwrap().is_verified());
+}
+
+#[tokio::test]
+async fn test_login_with_wrong_mfa_code() {
+    let server = MockServer::start().await;
+    let mut config = config(&server);
+    config.set_mfa_code("000000".to_string());
+
+    let mut session = session(config);
+    assert!(session.login().await.is_err());
+    assert!(session.get_secrets().is_none());
+    assert!(server.state().sid().is_none());
+}
+
+#[tokio::test]
+async fn test_login_with_wrong_password() {
+    let server = MockServer::start().await;
+    let mut config = config(&server);
+    config
+        .set_password("wrong".to_string())
+        .set_mfa_code("123456".to_string());
+
+    let mut session = session(config);
+    assert!(session.login().await.is_err());
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 0);
+}
+
+#[tokio::test]
+async fn test_login_with_totp() {
+    let server = MockServer::start_with(MockConfig {
+        mfa_code: None,
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config.set_mfa_secret(MFA_SECRET.to_string());
+
+    let mut session = session(config);
+    session.login().await.unwrap();
+    assert!(server.state().sid().is_some());
+}
+
+#[tokio::test]
+async fn test_login_with_rejected_totp() {
+    let server = MockServer::start_with(MockConfig {
+        mfa_code: Some("000000".to_string()),
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config.set_mfa_secret(MFA_SECRET.to_string());
+
+    // A rejected code is not retried with the adjacent windows' codes
+    let mut session = session(config);
+    assert!(session.login().await.is_err());
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 1);
+}
+
+#[tokio::test]
+async fn test_login_with_otp() {
+    let server = MockServer::start_with(MockConfig {
+        login_flow: LoginFlow::Otp { pin_fallback: false },
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config.set_phone("2065550811".to_string());
+
+    // The code is sent by sms, so the non-interactive login stops there
+    let mut session = session(config);
+    let err = session.login().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::LoginFailed);
+    assert_eq!(server.state().request_count("/sess/request_code"), 1);
+
+    let state = session.login_state().unwrap().clone();
+    assert!(matches!(state, LoginState::OtpSent { .. }));
+    let state = session.submit_otp(&state, "654321".to_string()).await.unwrap();
+    assert!(state.is_verified());
+    assert_eq!(
+        session.get_secrets().map(|creds| creds.get_sid()),
+        server.state().sid()
+    );
+}
+
+#[tokio::test]
+async fn test_login_with_pin_fallback() {
+    let server = MockServer::start_with(MockConfig {
+        login_flow: LoginFlow::Otp { pin_fallback: true },
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config.set_pin("1234".to_string());
+
+    let mut session = session(config);
+    session.login().await.unwrap();
+    assert_eq!(server.state().request_count("/sess/request_code"), 0);
+    assert!(server.state().sid().is_some());
+}
+
+#[tokio::test]
+async fn test_login_prefers_pin_over_otp() {
+    let server = MockServer::start_with(MockConfig {
+        login_flow: LoginFlow::Otp { pin_fallback: true },
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config
+        .set_phone("2065550811".to_string())
+        .set_pin("1234".to_string());
+
+    let mut by_pin = session(config.clone());
+    by_pin.login().await.unwrap();
+    assert_eq!(server.state().request_count("/sess/request_code"), 0);
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 1);
+
+    // A rejected PIN falls back to an OTP sent to the configured phone
+    config.set_pin("0000".to_string());
+    let mut by_otp = session(config);
+    let err = by_otp.login().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::LoginFailed);
+    assert_eq!(server.state().request_count("/sess/request_code"), 1);
+    assert!(matches!(by_otp.login_state(), Some(LoginState::OtpSent { .. })));
+}
+
+#[tokio::test]
+async fn test_login_with_pin_only() {
+    let server = MockServer::start_with(MockConfig {
+        login_flow: LoginFlow::Pin { pin_disabled: false },
+        ..Default::default()
+    })
+    .await;
+
+    let mut session = session(config(&server));
+    let state = session.start_login().await.unwrap();
+    assert!(matches!(state, LoginState::NeedsPin { .. }));
+    let state = session.submit_pin(&state, "1234".to_string()).await.unwrap();
+    assert!(state.is_verified());
+}
+
+#[tokio::test]
+async fn test_login_with_pin_disabled() {
+    let server = MockServer::start_with(MockConfig {
+        login_flow: LoginFlow::Pin { pin_disabled: true },
+        ..Default::default()
+    })
+    .await;
+    let mut config = config(&server);
+    config.set_pin("1234".to_string());
+
+    let mut session = session(config);
+    let err = session.login().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::LoginFailed);
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 0);
+}
+
+#[tokio::test]
+async fn test_login_with_stored_ftat() {
+    let server = MockServer::start().await;
+    let (ftat, sid) = server.state().issue_session();
+    let mut config = config(&server);
+    config.set_ftat(ftat.clone());
+
+    let mut session = session(config);
+    session.login().await.unwrap();
+
+    let creds = session.get_secrets().unwrap();
+    assert_eq!(creds.get_ftat(), ftat);
+    assert_ne!(creds.get_sid(), sid);
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 0);
+}
+
+#[tokio::test]
+async fn test_login_with_stored_login() {
+    let server = MockServer::start().await;
+    let (ftat, _) = server.state().issue_session();
+    let store = store("stored-login", ftat.clone(), "user");
+
+    let mut config = FtSessionConfig::default();
+    config.set_endpoint(Endpoint::new(&server.base_url()).unwrap());
+    assert!(FtSessionBuilder::new(config.clone()).is_err());
+
+    config.set_credential_store(store.clone());
+    let mut session = session(config);
+    session.login().await.unwrap();
+    assert_eq!(session.get_secrets().unwrap().get_ftat(), ftat);
+    store.clear().unwrap();
+}
+
+#[tokio::test]
+async fn test_login_ignores_foreign_ftat() {
+    let server = MockServer::start().await;
+    let (ftat, _) = server.state().issue_session();
+    let store = store("foreign-ftat", ftat, "other");
+
+    let mut config = config(&server);
+    config.set_credential_store(store.clone());
+    let mut session = session(config);
+    let state = session.start_login().await.unwrap();
+    assert!(matches!(state, LoginState::NeedsMfa { .. }));
+    store.clear().unwrap();
+}
+
+#[tokio::test]
+async fn test_login_ignores_unreadable_store() {
+    let server = MockServer::start().await;
+    let (ftat, _) = server.state().issue_session();
+    let store = store("unreadable", ftat, "user");
+    let path = std::env::temp_dir().join(format!("firstrade-{}-unreadable.json", std::process::id()));
+    let wrong_passphrase = FileCredentialStore::new(path, "other".to_string());
+    assert!(wrong_passphrase.load().is_err());
+
+    let mut config = config(&server);
+    config
+        .set_credential_store(Arc::new(wrong_passphrase))
+        .set_mfa_code("123456".to_string());
+    let mut session = session(config);
+    session.login().await.unwrap();
+    assert_eq!(server.state().request_count("/sess/verify_pin"), 1);
+    store.clear().unwrap();
+}
+
+#[tokio::test]
+async fn test_login_server_error() {
+    let server = MockServer::start().await;
+    server
+        .state()
+        .fail_next("/sess/login", firstrade_mock::Failure::Status(503));
+    let mut config = config(&server);
+    config.set_mfa_code("123456".to_string());
+
+    let mut session = session(config);
+    let err = session.login().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::ServerError);
+
+    // The failure is only injected once
+    session.login().await.unwrap();
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use chrono::NaiveDate;
+use common::{account, symbol};
+use firstrade::error::ErrorKind;
+use firstrade::models::account::{HistoryFilter, TransactionKind};
+use firstrade::stream::{QUOTE_STREAM_WATCHLIST, QuoteUpdate};
+use firstrade_mock::{Failure, MockServer, fixtures};
+use futures::StreamExt;
+use std::time::Duration;
+
+fn last_price(update: &QuoteUpdate) -> f64 {
+    match update {
+        QuoteUpdate::Quote { quote, .. } => quote.last.to_string().parse().unwrap(),
+        other => panic!("unexpected update {other:?}"),
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_quote_stream() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+    let mut stream = account.quote_stream([symbol("AAPL"), symbol("NOPE")], Duration::from_millis(100));
+
+    // Every symbol is sent on the first poll
+    let update = stream.next().await.unwrap();
+    assert_eq!(update.symbol(), &symbol("NOPE"));
+    assert!(matches!(update, QuoteUpdate::Failed { ref error, .. } if error.kind() == ErrorKind::NotFound));
+    let update = stream.next().await.unwrap();
+    assert_eq!(update.symbol(), &symbol("AAPL"));
+    assert_eq!(last_price(&update), 39.63);
+
+    // Unchanged quotes and repeated failures are skipped
+    stream.add(symbol("MSFT"));
+    let update = stream.next().await.unwrap();
+    assert_eq!(update.symbol(), &symbol("MSFT"));
+
+    server
+        .state()
+        .set_quote("AAPL", fixtures::stock_quote("AAPL", 41.0));
+    let update = stream.next().await.unwrap();
+    assert_eq!(update.symbol(), &symbol("AAPL"));
+    assert_eq!(last_price(&update), 41.0);
+
+    let handle = stream.handle();
+    assert!(handle.remove(&symbol("AAPL")));
+    assert!(!handle.remove(&symbol("AAPL")));
+    server
+        .state()
+        .set_quote("AAPL", fixtures::stock_quote("AAPL", 42.0));
+    let next = tokio::time::timeout(Duration::from_millis(350), stream.next()).await;
+    assert!(next.is_err());
+    assert_eq!(stream.symbols(), vec![symbol("MSFT"), symbol("NOPE")]);
+
+    // The stream's watchlist goes away with it
+    assert!(server.state().watchlist_id(QUOTE_STREAM_WATCHLIST).is_some());
+    drop(stream);
+    while server.state().watchlist_id(QUOTE_STREAM_WATCHLIST).is_some() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_quote_stream_single_request_per_poll() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+    let symbols: Vec<_> = (0..5).map(|i| symbol(&format!("T{i}"))).collect();
+    for symbol in &symbols {
+        server
+            .state()
+            .set_quote(symbol.as_str(), fixtures::stock_quote(symbol.as_str(), 10.0));
+    }
+
+    let mut stream = account.quote_stream(symbols.clone(), Duration::from_millis(100));
+    for _ in 0..5 {
+        stream.next().await.unwrap();
+    }
+    let list_id = server.state().watchlist_id(QUOTE_STREAM_WATCHLIST).unwrap();
+    let quote_path = format!("/private/watchlists/{list_id}");
+    assert_eq!(server.state().request_count("/private/watchlists"), 1);
+    assert_eq!(
+        server
+            .state()
+            .request_count(&format!("/private/watchlist/{list_id}")),
+        5
+    );
+    assert_eq!(server.state().request_count(&quote_path), 1);
+
+    // Every later poll is a single request, whatever the number of symbols
+    for (i, symbol) in symbols.iter().enumerate() {
+        server
+            .state()
+            .set_quote(symbol.as_str(), fixtures::stock_quote(symbol.as_str(), 11.0));
+        let update = stream.next().await.unwrap();
+        assert_eq!(update.symbol(), symbol);
+        assert_eq!(server.state().request_count(&quote_path), i + 2);
+    }
+    assert_eq!(server.state().request_count("/public/quote"), 0);
+}
+
+#[tokio::test]
+async fn test_history_stream() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    let filter = HistoryFilter::builder().per_page(2).build();
+    let items: Vec<_> = account.history_stream("ytd", filter).collect().await;
+    assert_eq!(items.len(), 5);
+    assert_eq!(items[4].as_ref().unwrap().kind(), TransactionKind::Interest);
+    assert_eq!(server.state().request_count("/private/account_history"), 3);
+
+    // The last page is full, `total` ends it without an extra request
+    let filter = HistoryFilter::builder().per_page(5).build();
+    let items: Vec<_> = account.history_stream("ytd", filter).collect().await;
+    assert_eq!(items.len(), 5);
+    assert_eq!(server.state().request_count("/private/account_history"), 4);
+
+    let filter = HistoryFilter::builder()
+        .start(NaiveDate::from_ymd_opt(2025, 8, 4).unwrap())
+        .end(NaiveDate::from_ymd_opt(2025, 8, 15).unwrap())
+        .per_page(2)
+        .build();
+    let items: Vec<_> = account
+        .history_stream("ytd", filter)
+        .map(|item| item.unwrap())
+        .map(|item| (item.parsed_report_date().unwrap().to_string(), item.kind()))
+        .collect()
+        .await;
+    assert_eq!(
+        items,
+        [
+            ("2025-08-04".to_string(), TransactionKind::Buy),
+            ("2025-08-05".to_string(), TransactionKind::Sell),
+            ("2025-08-15".to_string(), TransactionKind::Dividend),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_history_stream_error() {
+    let server = MockServer::start().await;
+    let account = account(&server);
+
+    server
+        .state()
+        .fail_next("/private/account_history", Failure::Status(503));
+    let items: Vec<_> = account
+        .history_stream("ytd", HistoryFilter::default())
+        .collect()
+        .await;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].as_ref().err().unwrap().kind(), ErrorKind::ServerError);
+}